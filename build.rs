@@ -0,0 +1,154 @@
+use proc_macro2::TokenStream;
+use quote::{
+    format_ident,
+    quote,
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    fs,
+    path::PathBuf,
+};
+
+#[path = "src/bin/generatelib/function.rs"]
+mod function;
+
+use function::{
+    generate_function_wrapper,
+    FunctionParam,
+    FunctionWrapper,
+};
+
+#[derive(Deserialize)]
+struct Functions {
+    function_signatures: BTreeMap<String, Signature>,
+}
+
+#[derive(Deserialize)]
+struct Signature {
+    description: Option<String>,
+    return_type: serde_json::Value,
+    #[serde(default)]
+    parameters: Vec<Parameter>,
+    variadic_parameter: Option<Parameter>,
+}
+
+#[derive(Deserialize)]
+struct Parameter {
+    name: String,
+    description: Option<String>,
+    #[serde(rename = "type")]
+    type_: serde_json::Value,
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_uppercase() {
+            out.push('_');
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// The `ToArg` kind for a parameter. `dynamic` elements of collections become new
+/// generic parameters, added to `generics`.
+fn arg_kind(t: &serde_json::Value, nested: bool, generics: &mut Vec<TokenStream>) -> TokenStream {
+    match t {
+        serde_json::Value::String(s) => match s.as_str() {
+            "string" => quote!(PrimExpr<String>),
+            "bool" => quote!(PrimExpr<bool>),
+            "number" => quote!(Number),
+            "dynamic" if nested => {
+                let g = format_ident!("T{}", generics.len());
+                generics.push(quote!(#g: Ref));
+                quote!(#g)
+            },
+            "dynamic" => quote!(Dynamic),
+            _ => panic!("Unhandled function parameter type {}", s),
+        },
+        serde_json::Value::Array(a) => {
+            let inner = arg_kind(&a[1], true, generics);
+            match a[0].as_str().unwrap() {
+                "list" => quote!(ListRef<#inner>),
+                "set" => quote!(SetRef<#inner>),
+                "map" => quote!(RecRef<#inner>),
+                s => panic!("Unhandled function parameter collection type {}", s),
+            }
+        },
+        _ => panic!("Unhandled function parameter type {}", t),
+    }
+}
+
+/// The reference type for a return value. `number` and `dynamic` are left to the
+/// caller via generic parameters.
+fn return_type(t: &serde_json::Value, generics: &mut Vec<TokenStream>) -> TokenStream {
+    match t {
+        serde_json::Value::String(s) => match s.as_str() {
+            "string" => quote!(PrimExpr<String>),
+            "bool" => quote!(PrimExpr<bool>),
+            "number" => {
+                generics.push(quote!(N: PrimNumber));
+                quote!(PrimExpr<N>)
+            },
+            "dynamic" => {
+                generics.push(quote!(R: Ref));
+                quote!(R)
+            },
+            _ => panic!("Unhandled function return type {}", s),
+        },
+        serde_json::Value::Array(a) => {
+            let inner = return_type(&a[1], generics);
+            match a[0].as_str().unwrap() {
+                "list" => quote!(ListRef<#inner>),
+                "set" => quote!(SetRef<#inner>),
+                "map" => quote!(RecRef<#inner>),
+                s => panic!("Unhandled function return collection type {}", s),
+            }
+        },
+        _ => panic!("Unhandled function return type {}", t),
+    }
+}
+
+fn main() {
+    let source = "src/functions.json";
+    println!("cargo:rerun-if-changed={}", source);
+    let functions: Functions = serde_json::from_slice(&fs::read(source).unwrap()).unwrap();
+    let mut out = vec![];
+    for (name, sig) in functions.function_signatures {
+        // Namespaced aliases of the same functions
+        if name.contains("::") {
+            continue;
+        }
+        let mut generics = vec![];
+        let mut param = |p: &Parameter| FunctionParam {
+            ident: format_ident!("{}", snake_case(&p.name)),
+            kind: arg_kind(&p.type_, false, &mut generics),
+            name: p.name.clone(),
+            description: p.description.clone(),
+        };
+        let params = sig.parameters.iter().map(&mut param).collect();
+        let variadic = sig.variadic_parameter.as_ref().map(&mut param);
+        let ret = return_type(&sig.return_type, &mut generics);
+        out.push(generate_function_wrapper(FunctionWrapper {
+            fn_ident: format_ident!("tf_{}", name),
+            description: sig
+                .description
+                .unwrap_or_else(|| format!("Generates a call to Terraform function `{}`.", name)),
+            tf_name: name,
+            generics,
+            params,
+            variadic,
+            ret,
+            prelude: quote!(),
+        }));
+    }
+    fs::write(
+        PathBuf::from(env::var("OUT_DIR").unwrap()).join("functions.rs"),
+        quote!(#(#out) *).to_string(),
+    ).unwrap();
+}
@@ -0,0 +1,267 @@
+use proc_macro2::TokenStream;
+use quote::{
+    format_ident,
+    quote,
+};
+use syn::{
+    parse_macro_input,
+    Data,
+    DeriveInput,
+    Expr,
+    ExprLit,
+    Fields,
+    Lit,
+    LitStr,
+    Meta,
+};
+
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    tf_id: String,
+    description: Option<String>,
+}
+
+/// Apply a serde `rename_all` rule to a (snake case) field name.
+fn rename_field(rule: &str, name: &str) -> Option<String> {
+    let pascal = || {
+        name.split('_').map(|s| {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        }).collect::<String>()
+    };
+    Some(match rule {
+        "lowercase" | "snake_case" => name.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(c) => c.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        },
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    })
+}
+
+fn unsupported(meta: &syn::meta::ParseNestedMeta) -> syn::Error {
+    meta.error(format!(
+        "serde attribute `{}` isn't supported by the terrars derives since the declarations wouldn't match the json",
+        meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default()
+    ))
+}
+
+fn get_fields(input: &DeriveInput) -> Result<Vec<Field>, syn::Error> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "Only structs are supported"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(input, "Only structs with named fields are supported"));
+    };
+    let mut rename_all = None;
+    for attr in &input.attrs {
+        if attr.path().is_ident("serde") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    if !meta.input.peek(syn::Token![=]) {
+                        return Err(unsupported(&meta));
+                    }
+                    let rule = meta.value()?.parse::<LitStr>()?;
+                    if rename_field(&rule.value(), "").is_none() {
+                        return Err(syn::Error::new_spanned(rule, "Unknown rename_all rule"));
+                    }
+                    rename_all = Some(rule.value());
+                } else if meta.path.is_ident("transparent") || meta.path.is_ident("rename_all_fields") {
+                    return Err(unsupported(&meta));
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<TokenStream>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|_| Ok(()))?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+    let mut out = vec![];
+    for field in &fields.named {
+        let ident = field.ident.clone().unwrap();
+
+        // Use the serde name so the var-file and output json match the declarations
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        let mut tf_id = match &rename_all {
+            Some(rule) => rename_field(rule, name).unwrap(),
+            None => name.to_string(),
+        };
+        let mut skip = false;
+        let mut doc = vec![];
+        for attr in &field.attrs {
+            if attr.path().is_ident("serde") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        if !meta.input.peek(syn::Token![=]) {
+                            return Err(unsupported(&meta));
+                        }
+                        tf_id = meta.value()?.parse::<LitStr>()?.value();
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                    } else if meta.path.is_ident("flatten") ||
+                        meta.path.is_ident("skip_serializing") ||
+                        meta.path.is_ident("skip_serializing_if") ||
+                        meta.path.is_ident("skip_deserializing") {
+                        return Err(unsupported(&meta));
+                    } else if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<TokenStream>()?;
+                    } else if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|_| Ok(()))?;
+                    }
+                    Ok(())
+                })?;
+            } else if attr.path().is_ident("doc") {
+                if let Meta::NameValue(m) = &attr.meta {
+                    if let Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) = &m.value {
+                        doc.push(s.value().trim().to_string());
+                    }
+                }
+            }
+        }
+        if skip {
+            continue;
+        }
+        out.push(Field {
+            ident,
+            ty: field.ty.clone(),
+            tf_id,
+            description: if doc.is_empty() {
+                None
+            } else {
+                Some(doc.join("\n"))
+            },
+        });
+    }
+    Ok(out)
+}
+
+/// Declare a stack variable for each field. Generates `{Name}Variables` with a
+/// `Variable` per field, returned from `TfVariables::declare`. Field doc comments
+/// become variable descriptions.
+#[proc_macro_derive(TfVariables)]
+pub fn derive_variables(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match get_fields(&input) {
+        Ok(f) => f,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let vis = &input.vis;
+    let name = &input.ident;
+    let variables_name = format_ident!("{}Variables", name);
+    let mut handle_fields = vec![];
+    let mut declare_fields = vec![];
+    for field in fields {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let tf_id = &field.tf_id;
+        handle_fields.push(quote!{
+            pub #ident: terrars::Variable<<#ty as terrars::TfValue>::V>
+        });
+        let set_description = match &field.description {
+            Some(d) => quote!(.set_description(#d)),
+            None => quote!(),
+        };
+        declare_fields.push(quote!{
+            #ident: terrars::BuildVariable {
+                tf_id: #tf_id.into()
+            }.build::<<#ty as terrars::TfValue>::V>(stack).set_nullable(
+                <#ty as terrars::TfValue>::nullable(),
+            ).set_sensitive(<#ty as terrars::TfValue>::sensitive()) #set_description
+        });
+    }
+    quote!{
+        #vis struct #variables_name {
+            #(#handle_fields,) *
+        }
+
+        impl terrars::TfVariables for #name {
+            type Variables = #variables_name;
+
+            fn declare(stack: &mut terrars::Stack) -> #variables_name {
+                #variables_name {
+                    #(#declare_fields,) *
+                }
+            }
+        }
+    }.into()
+}
+
+/// Declare a stack output for each field. Generates `Build{Name}` with the value of
+/// each output and a `build` method returning `{Name}Outputs`, with an `OutputImpl`
+/// per field. Field doc comments become output descriptions.
+#[proc_macro_derive(TfOutputs)]
+pub fn derive_outputs(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match get_fields(&input) {
+        Ok(f) => f,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let vis = &input.vis;
+    let name = &input.ident;
+    let build_name = format_ident!("Build{}", name);
+    let outputs_name = format_ident!("{}Outputs", name);
+    let mut value_fields = vec![];
+    let mut handle_fields = vec![];
+    let mut build_fields = vec![];
+    let mut idents = vec![];
+    for field in fields {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let tf_id = &field.tf_id;
+        value_fields.push(quote!{
+            pub #ident: <#ty as terrars::TfValue>::F
+        });
+        handle_fields.push(quote!{
+            pub #ident: std::rc::Rc<terrars::OutputImpl<#ty>>
+        });
+        let set_description = match &field.description {
+            Some(d) => quote!{
+                #ident.set_description(#d);
+            },
+            None => quote!(),
+        };
+        build_fields.push(quote!{
+            let #ident = terrars::BuildOutput::<#ty> {
+                tf_id: #tf_id.into(),
+                value: self.#ident,
+            }.build(stack);
+            #set_description
+        });
+        idents.push(ident.clone());
+    }
+    quote!{
+        #vis struct #build_name {
+            #(#value_fields,) *
+        }
+
+        #vis struct #outputs_name {
+            #(#handle_fields,) *
+        }
+
+        impl #build_name {
+            pub fn build(self, stack: &mut terrars::Stack) -> #outputs_name {
+                #(#build_fields) * #outputs_name {
+                    #(#idents,) *
+                }
+            }
+        }
+
+        impl terrars::TfOutputs for #name {
+            type Build = #build_name;
+        }
+    }.into()
+}
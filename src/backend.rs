@@ -0,0 +1,835 @@
+use serde::Serialize;
+
+/// Where Terraform stores state. Set this in `BuildStack`. If not set, the stack
+/// uses the `local` backend with state stored in `state.tfstate` next to the stack
+/// file.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    Local(BackendLocal),
+    S3(BackendS3),
+    Gcs(BackendGcs),
+    Azurerm(BackendAzurerm),
+    Http(BackendHttp),
+    Pg(BackendPg),
+    Consul(BackendConsul),
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::Local(BuildBackendLocal {}.build())
+    }
+}
+
+// Local
+#[derive(Serialize, Clone)]
+pub struct BackendLocal {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_dir: Option<String>,
+}
+
+impl BackendLocal {
+    /// Set the path of the state file, relative to the stack directory.
+    pub fn set_path(mut self, v: impl ToString) -> Self {
+        self.path = Some(v.to_string());
+        self
+    }
+
+    /// Set the directory non-default workspace state is stored in.
+    pub fn set_workspace_dir(mut self, v: impl ToString) -> Self {
+        self.workspace_dir = Some(v.to_string());
+        self
+    }
+}
+
+impl From<BackendLocal> for Backend {
+    fn from(value: BackendLocal) -> Self {
+        Backend::Local(value)
+    }
+}
+
+/// Store state in a local file, `state.tfstate` in the stack directory unless
+/// another path is set.
+pub struct BuildBackendLocal {}
+
+impl BuildBackendLocal {
+    pub fn build(self) -> BackendLocal {
+        BackendLocal {
+            path: Some("state.tfstate".into()),
+            workspace_dir: None,
+        }
+    }
+}
+
+// S3
+#[derive(Serialize, Clone)]
+pub struct BackendS3 {
+    bucket: String,
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encrypt: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kms_key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    acl: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dynamodb_table: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_lockfile: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_key_prefix: Option<String>,
+}
+
+impl BackendS3 {
+    pub fn set_region(mut self, v: impl ToString) -> Self {
+        self.region = Some(v.to_string());
+        self
+    }
+
+    /// Set the AWS shared config profile used for credentials.
+    pub fn set_profile(mut self, v: impl ToString) -> Self {
+        self.profile = Some(v.to_string());
+        self
+    }
+
+    pub fn set_access_key(mut self, v: impl ToString) -> Self {
+        self.access_key = Some(v.to_string());
+        self
+    }
+
+    pub fn set_secret_key(mut self, v: impl ToString) -> Self {
+        self.secret_key = Some(v.to_string());
+        self
+    }
+
+    /// Enable server side encryption of the state file.
+    pub fn set_encrypt(mut self, v: bool) -> Self {
+        self.encrypt = Some(v);
+        self
+    }
+
+    pub fn set_kms_key_id(mut self, v: impl ToString) -> Self {
+        self.kms_key_id = Some(v.to_string());
+        self
+    }
+
+    /// Set the canned ACL applied to the state file.
+    pub fn set_acl(mut self, v: impl ToString) -> Self {
+        self.acl = Some(v.to_string());
+        self
+    }
+
+    /// Lock state using this DynamoDB table.
+    pub fn set_dynamodb_table(mut self, v: impl ToString) -> Self {
+        self.dynamodb_table = Some(v.to_string());
+        self
+    }
+
+    /// Lock state using a lock file stored next to the state in S3.
+    pub fn set_use_lockfile(mut self, v: bool) -> Self {
+        self.use_lockfile = Some(v);
+        self
+    }
+
+    pub fn set_workspace_key_prefix(mut self, v: impl ToString) -> Self {
+        self.workspace_key_prefix = Some(v.to_string());
+        self
+    }
+}
+
+impl From<BackendS3> for Backend {
+    fn from(value: BackendS3) -> Self {
+        Backend::S3(value)
+    }
+}
+
+/// Store state in an AWS S3 bucket.
+pub struct BuildBackendS3 {
+    pub bucket: String,
+    /// Path of the state file within the bucket.
+    pub key: String,
+}
+
+impl BuildBackendS3 {
+    pub fn build(self) -> BackendS3 {
+        BackendS3 {
+            bucket: self.bucket,
+            key: self.key,
+            region: None,
+            profile: None,
+            access_key: None,
+            secret_key: None,
+            encrypt: None,
+            kms_key_id: None,
+            acl: None,
+            dynamodb_table: None,
+            use_lockfile: None,
+            workspace_key_prefix: None,
+        }
+    }
+}
+
+// GCS
+#[derive(Serialize, Clone)]
+pub struct BackendGcs {
+    bucket: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credentials: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    impersonate_service_account: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encryption_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kms_encryption_key: Option<String>,
+}
+
+impl BackendGcs {
+    /// Set the directory within the bucket state is stored in.
+    pub fn set_prefix(mut self, v: impl ToString) -> Self {
+        self.prefix = Some(v.to_string());
+        self
+    }
+
+    /// Set the path or contents of a service account key file.
+    pub fn set_credentials(mut self, v: impl ToString) -> Self {
+        self.credentials = Some(v.to_string());
+        self
+    }
+
+    pub fn set_impersonate_service_account(mut self, v: impl ToString) -> Self {
+        self.impersonate_service_account = Some(v.to_string());
+        self
+    }
+
+    /// Set a customer supplied encryption key (base64) for the state.
+    pub fn set_encryption_key(mut self, v: impl ToString) -> Self {
+        self.encryption_key = Some(v.to_string());
+        self
+    }
+
+    /// Set a Cloud KMS key used to encrypt the state.
+    pub fn set_kms_encryption_key(mut self, v: impl ToString) -> Self {
+        self.kms_encryption_key = Some(v.to_string());
+        self
+    }
+}
+
+impl From<BackendGcs> for Backend {
+    fn from(value: BackendGcs) -> Self {
+        Backend::Gcs(value)
+    }
+}
+
+/// Store state in a Google Cloud Storage bucket.
+pub struct BuildBackendGcs {
+    pub bucket: String,
+}
+
+impl BuildBackendGcs {
+    pub fn build(self) -> BackendGcs {
+        BackendGcs {
+            bucket: self.bucket,
+            prefix: None,
+            credentials: None,
+            impersonate_service_account: None,
+            encryption_key: None,
+            kms_encryption_key: None,
+        }
+    }
+}
+
+// Azurerm
+#[derive(Serialize, Clone)]
+pub struct BackendAzurerm {
+    storage_account_name: String,
+    container_name: String,
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resource_group_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subscription_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sas_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_azuread_auth: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_oidc: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_msi: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<String>,
+}
+
+impl BackendAzurerm {
+    pub fn set_resource_group_name(mut self, v: impl ToString) -> Self {
+        self.resource_group_name = Some(v.to_string());
+        self
+    }
+
+    pub fn set_subscription_id(mut self, v: impl ToString) -> Self {
+        self.subscription_id = Some(v.to_string());
+        self
+    }
+
+    pub fn set_tenant_id(mut self, v: impl ToString) -> Self {
+        self.tenant_id = Some(v.to_string());
+        self
+    }
+
+    pub fn set_client_id(mut self, v: impl ToString) -> Self {
+        self.client_id = Some(v.to_string());
+        self
+    }
+
+    pub fn set_client_secret(mut self, v: impl ToString) -> Self {
+        self.client_secret = Some(v.to_string());
+        self
+    }
+
+    /// Set the storage account access key.
+    pub fn set_access_key(mut self, v: impl ToString) -> Self {
+        self.access_key = Some(v.to_string());
+        self
+    }
+
+    pub fn set_sas_token(mut self, v: impl ToString) -> Self {
+        self.sas_token = Some(v.to_string());
+        self
+    }
+
+    /// Authenticate to the storage account with Azure AD instead of an access key.
+    pub fn set_use_azuread_auth(mut self, v: bool) -> Self {
+        self.use_azuread_auth = Some(v);
+        self
+    }
+
+    pub fn set_use_oidc(mut self, v: bool) -> Self {
+        self.use_oidc = Some(v);
+        self
+    }
+
+    /// Authenticate with a managed service identity.
+    pub fn set_use_msi(mut self, v: bool) -> Self {
+        self.use_msi = Some(v);
+        self
+    }
+
+    /// Set the Azure cloud, ex: `public`, `china`, `usgovernment`.
+    pub fn set_environment(mut self, v: impl ToString) -> Self {
+        self.environment = Some(v.to_string());
+        self
+    }
+}
+
+impl From<BackendAzurerm> for Backend {
+    fn from(value: BackendAzurerm) -> Self {
+        Backend::Azurerm(value)
+    }
+}
+
+/// Store state in an Azure blob storage container.
+pub struct BuildBackendAzurerm {
+    pub storage_account_name: String,
+    pub container_name: String,
+    /// Name of the state blob within the container.
+    pub key: String,
+}
+
+impl BuildBackendAzurerm {
+    pub fn build(self) -> BackendAzurerm {
+        BackendAzurerm {
+            storage_account_name: self.storage_account_name,
+            container_name: self.container_name,
+            key: self.key,
+            resource_group_name: None,
+            subscription_id: None,
+            tenant_id: None,
+            client_id: None,
+            client_secret: None,
+            access_key: None,
+            sas_token: None,
+            use_azuread_auth: None,
+            use_oidc: None,
+            use_msi: None,
+            environment: None,
+        }
+    }
+}
+
+// Http
+#[derive(Serialize, Clone)]
+pub struct BackendHttp {
+    address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lock_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lock_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unlock_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unlock_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_cert_verification: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_max: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_wait_min: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_wait_max: Option<i64>,
+}
+
+impl BackendHttp {
+    /// Set the HTTP method used to write state (default `POST`).
+    pub fn set_update_method(mut self, v: impl ToString) -> Self {
+        self.update_method = Some(v.to_string());
+        self
+    }
+
+    /// Enable locking by setting the address lock requests are sent to.
+    pub fn set_lock_address(mut self, v: impl ToString) -> Self {
+        self.lock_address = Some(v.to_string());
+        self
+    }
+
+    pub fn set_lock_method(mut self, v: impl ToString) -> Self {
+        self.lock_method = Some(v.to_string());
+        self
+    }
+
+    pub fn set_unlock_address(mut self, v: impl ToString) -> Self {
+        self.unlock_address = Some(v.to_string());
+        self
+    }
+
+    pub fn set_unlock_method(mut self, v: impl ToString) -> Self {
+        self.unlock_method = Some(v.to_string());
+        self
+    }
+
+    pub fn set_username(mut self, v: impl ToString) -> Self {
+        self.username = Some(v.to_string());
+        self
+    }
+
+    pub fn set_password(mut self, v: impl ToString) -> Self {
+        self.password = Some(v.to_string());
+        self
+    }
+
+    pub fn set_skip_cert_verification(mut self, v: bool) -> Self {
+        self.skip_cert_verification = Some(v);
+        self
+    }
+
+    pub fn set_retry_max(mut self, v: i64) -> Self {
+        self.retry_max = Some(v);
+        self
+    }
+
+    /// Set the minimum seconds to wait between retries.
+    pub fn set_retry_wait_min(mut self, v: i64) -> Self {
+        self.retry_wait_min = Some(v);
+        self
+    }
+
+    /// Set the maximum seconds to wait between retries.
+    pub fn set_retry_wait_max(mut self, v: i64) -> Self {
+        self.retry_wait_max = Some(v);
+        self
+    }
+}
+
+impl From<BackendHttp> for Backend {
+    fn from(value: BackendHttp) -> Self {
+        Backend::Http(value)
+    }
+}
+
+/// Store state via a REST endpoint.
+pub struct BuildBackendHttp {
+    pub address: String,
+}
+
+impl BuildBackendHttp {
+    pub fn build(self) -> BackendHttp {
+        BackendHttp {
+            address: self.address,
+            update_method: None,
+            lock_address: None,
+            lock_method: None,
+            unlock_address: None,
+            unlock_method: None,
+            username: None,
+            password: None,
+            skip_cert_verification: None,
+            retry_max: None,
+            retry_wait_min: None,
+            retry_wait_max: None,
+        }
+    }
+}
+
+// Pg
+#[derive(Serialize, Clone)]
+pub struct BackendPg {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conn_str: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_schema_creation: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_table_creation: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_index_creation: Option<bool>,
+}
+
+impl BackendPg {
+    /// Set the postgres connection string. If not set, Terraform reads it from
+    /// `PG_CONN_STR`.
+    pub fn set_conn_str(mut self, v: impl ToString) -> Self {
+        self.conn_str = Some(v.to_string());
+        self
+    }
+
+    pub fn set_schema_name(mut self, v: impl ToString) -> Self {
+        self.schema_name = Some(v.to_string());
+        self
+    }
+
+    pub fn set_skip_schema_creation(mut self, v: bool) -> Self {
+        self.skip_schema_creation = Some(v);
+        self
+    }
+
+    pub fn set_skip_table_creation(mut self, v: bool) -> Self {
+        self.skip_table_creation = Some(v);
+        self
+    }
+
+    pub fn set_skip_index_creation(mut self, v: bool) -> Self {
+        self.skip_index_creation = Some(v);
+        self
+    }
+}
+
+impl From<BackendPg> for Backend {
+    fn from(value: BackendPg) -> Self {
+        Backend::Pg(value)
+    }
+}
+
+/// Store state in a Postgres database.
+pub struct BuildBackendPg {}
+
+impl BuildBackendPg {
+    pub fn build(self) -> BackendPg {
+        BackendPg {
+            conn_str: None,
+            schema_name: None,
+            skip_schema_creation: None,
+            skip_table_creation: None,
+            skip_index_creation: None,
+        }
+    }
+}
+
+// Consul
+#[derive(Serialize, Clone)]
+pub struct BackendConsul {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    datacenter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    http_auth: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gzip: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lock: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ca_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cert_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_file: Option<String>,
+}
+
+impl BackendConsul {
+    /// Set the Consul agent address, ex: `consul.example.com:8500`.
+    pub fn set_address(mut self, v: impl ToString) -> Self {
+        self.address = Some(v.to_string());
+        self
+    }
+
+    /// Set `http` or `https`.
+    pub fn set_scheme(mut self, v: impl ToString) -> Self {
+        self.scheme = Some(v.to_string());
+        self
+    }
+
+    pub fn set_datacenter(mut self, v: impl ToString) -> Self {
+        self.datacenter = Some(v.to_string());
+        self
+    }
+
+    pub fn set_access_token(mut self, v: impl ToString) -> Self {
+        self.access_token = Some(v.to_string());
+        self
+    }
+
+    /// Set HTTP basic auth credentials as `user:password`.
+    pub fn set_http_auth(mut self, v: impl ToString) -> Self {
+        self.http_auth = Some(v.to_string());
+        self
+    }
+
+    pub fn set_gzip(mut self, v: bool) -> Self {
+        self.gzip = Some(v);
+        self
+    }
+
+    pub fn set_lock(mut self, v: bool) -> Self {
+        self.lock = Some(v);
+        self
+    }
+
+    pub fn set_ca_file(mut self, v: impl ToString) -> Self {
+        self.ca_file = Some(v.to_string());
+        self
+    }
+
+    pub fn set_cert_file(mut self, v: impl ToString) -> Self {
+        self.cert_file = Some(v.to_string());
+        self
+    }
+
+    pub fn set_key_file(mut self, v: impl ToString) -> Self {
+        self.key_file = Some(v.to_string());
+        self
+    }
+}
+
+impl From<BackendConsul> for Backend {
+    fn from(value: BackendConsul) -> Self {
+        Backend::Consul(value)
+    }
+}
+
+/// Store state in the Consul KV store.
+pub struct BuildBackendConsul {
+    /// KV path the state is stored at.
+    pub path: String,
+}
+
+impl BuildBackendConsul {
+    pub fn build(self) -> BackendConsul {
+        BackendConsul {
+            path: self.path,
+            address: None,
+            scheme: None,
+            datacenter: None,
+            access_token: None,
+            http_auth: None,
+            gzip: None,
+            lock: None,
+            ca_file: None,
+            cert_file: None,
+            key_file: None,
+        }
+    }
+}
+
+// Cloud
+/// Which HCP Terraform (Terraform Cloud) workspaces the stack uses.
+#[derive(Serialize, Clone)]
+pub struct CloudWorkspaces {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+}
+
+impl CloudWorkspaces {
+    /// Use the single workspace `name`.
+    pub fn name(name: impl ToString) -> Self {
+        CloudWorkspaces {
+            name: Some(name.to_string()),
+            tags: vec![],
+            project: None,
+        }
+    }
+
+    /// Use any workspace with all of `tags`, selected with `terraform workspace`.
+    pub fn tags(tags: Vec<String>) -> Self {
+        CloudWorkspaces {
+            name: None,
+            tags,
+            project: None,
+        }
+    }
+
+    /// Set the project new workspaces are created in.
+    pub fn set_project(mut self, v: impl ToString) -> Self {
+        self.project = Some(v.to_string());
+        self
+    }
+}
+
+/// Store state and run operations in HCP Terraform (Terraform Cloud) or Terraform
+/// Enterprise. Set this in `BuildStack`.
+#[derive(Serialize, Clone)]
+pub struct Cloud {
+    organization: String,
+    workspaces: CloudWorkspaces,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+impl Cloud {
+    /// Set the Terraform Enterprise hostname. Defaults to `app.terraform.io`.
+    pub fn set_hostname(mut self, v: impl ToString) -> Self {
+        self.hostname = Some(v.to_string());
+        self
+    }
+
+    /// Set the API token. Prefer `terraform login` or the `TF_TOKEN_*` environment
+    /// variables.
+    pub fn set_token(mut self, v: impl ToString) -> Self {
+        self.token = Some(v.to_string());
+        self
+    }
+}
+
+pub struct BuildCloud {
+    pub organization: String,
+    pub workspaces: CloudWorkspaces,
+}
+
+impl BuildCloud {
+    pub fn build(self) -> Cloud {
+        Cloud {
+            organization: self.organization,
+            workspaces: self.workspaces,
+            hostname: None,
+            token: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value,
+    };
+    use crate::BuildStack;
+    use super::{
+        BuildBackendS3,
+        BuildCloud,
+        CloudWorkspaces,
+    };
+
+    fn terraform(stack: BuildStack) -> Value {
+        let out: Value = serde_json::from_slice(&stack.build().serialize().unwrap()).unwrap();
+        out["terraform"].clone()
+    }
+
+    fn backend(stack: BuildStack) -> Value {
+        terraform(stack)["backend"].clone()
+    }
+
+    #[test]
+    fn local_default() {
+        assert_eq!(backend(BuildStack::default()), json!({
+            "local": {
+                "path": "state.tfstate"
+            }
+        }));
+    }
+
+    #[test]
+    fn s3() {
+        assert_eq!(backend(BuildStack {
+            backend: BuildBackendS3 {
+                bucket: "b".into(),
+                key: "k.tfstate".into(),
+            }.build().set_region("us-east-1").set_use_lockfile(true).into(),
+            ..Default::default()
+        }), json!({
+            "s3": {
+                "bucket": "b",
+                "key": "k.tfstate",
+                "region": "us-east-1",
+                "use_lockfile": true
+            }
+        }));
+    }
+
+    #[test]
+    fn terraform_settings() {
+        assert_eq!(terraform(BuildStack {
+            cloud: Some(BuildCloud {
+                organization: "org".into(),
+                workspaces: CloudWorkspaces::tags(vec!["app".into()]).set_project("p"),
+            }.build()),
+            required_version: Some("~> 1.9".into()),
+            experiments: vec!["x".into()],
+            provider_meta: [("aws".to_string(), json!({
+                "module_name": "m"
+            }))].into_iter().collect(),
+            ..Default::default()
+        }), json!({
+            "cloud": {
+                "organization": "org",
+                "workspaces": {
+                    "tags": ["app"],
+                    "project": "p"
+                }
+            },
+            "required_version": "~> 1.9",
+            "experiments": ["x"],
+            "provider_meta": {
+                "aws": {
+                    "module_name": "m"
+                }
+            },
+            "required_providers": {}
+        }));
+    }
+}
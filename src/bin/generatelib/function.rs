@@ -0,0 +1,73 @@
+use proc_macro2::{
+    Ident,
+    TokenStream,
+};
+use quote::quote;
+
+pub struct FunctionParam {
+    pub ident: Ident,
+    /// The `ToArg` kind, ex: `PrimExpr<String>`.
+    pub kind: TokenStream,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+pub struct FunctionWrapper {
+    pub fn_ident: Ident,
+    /// The function name in Terraform, ex: `provider::aws::arn_parse`.
+    pub tf_name: String,
+    pub description: String,
+    pub generics: Vec<TokenStream>,
+    pub params: Vec<FunctionParam>,
+    pub variadic: Option<FunctionParam>,
+    pub ret: TokenStream,
+    /// Statements run before building the call expression.
+    pub prelude: TokenStream,
+}
+
+/// Generate a `tf_*` function that builds a call expression from its arguments.
+/// Shared by `build.rs` (built-in functions) and the generator (provider functions).
+pub fn generate_function_wrapper(f: FunctionWrapper) -> TokenStream {
+    let mut param_docs = vec![];
+    let mut params = vec![];
+    let mut args = vec![];
+    for p in &f.params {
+        let ident = &p.ident;
+        let kind = &p.kind;
+        params.push(quote!(#ident: impl ToArg < #kind >));
+        args.push(quote!(#ident.to_arg()));
+        if let Some(d) = &p.description {
+            param_docs.push(format!("* `{}` - {}", p.name, d));
+        }
+    }
+    let tf_name = &f.tf_name;
+    let call = match &f.variadic {
+        Some(p) => {
+            let ident = &p.ident;
+            let kind = &p.kind;
+            params.push(quote!(#ident: impl ToArgs < #kind >));
+            if let Some(d) = &p.description {
+                param_docs.push(format!("* `{}` (variadic) - {}", p.name, d));
+            }
+            quote!{
+                let mut call_args = vec![#(#args), *];
+                call_args.extend(#ident.to_args());
+                call_func(stack, #tf_name, call_args)
+            }
+        },
+        None => quote!(call_func(stack, #tf_name, vec![#(#args), *])),
+    };
+    let mut doc = f.description;
+    if !param_docs.is_empty() {
+        doc = format!("{}\n\n{}", doc, param_docs.join("\n"));
+    }
+    let fn_ident = &f.fn_ident;
+    let generics = &f.generics;
+    let ret = &f.ret;
+    let prelude = &f.prelude;
+    quote!{
+        #[doc = #doc] pub fn #fn_ident < #(#generics), * >(stack:& Stack, #(#params), *) -> #ret {
+            #prelude #call
+        }
+    }
+}
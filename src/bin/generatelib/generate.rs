@@ -6,6 +6,11 @@ use quote::{
     format_ident,
     quote,
 };
+use super::function::{
+    generate_function_wrapper,
+    FunctionParam,
+    FunctionWrapper,
+};
 use super::sourceschema::{
     ScalarTypeKey,
     ValueBehaviorHelper,
@@ -18,6 +23,9 @@ use super::sourceschema::{
     NestedBlock,
     Block,
     NestingMode,
+    Function,
+    FunctionParameter,
+    FunctionType,
 };
 
 pub fn generate_simple_type(t: &ScalarTypeKey) -> (TokenStream, Option<(TokenStream, TokenStream)>) {
@@ -26,6 +34,7 @@ pub fn generate_simple_type(t: &ScalarTypeKey) -> (TokenStream, Option<(TokenStr
         ScalarTypeKey::Integer => quote!(i64),
         ScalarTypeKey::String => quote!(String),
         ScalarTypeKey::Bool => quote!(bool),
+        ScalarTypeKey::Dynamic => return (quote!(DynamicField), None),
     };
     (quote!(PrimField < #raw >), Some((quote!(PrimExpr), quote!(PrimExpr < #raw >))))
 }
@@ -116,6 +125,15 @@ pub struct TopLevelFields {
     pub builder_fields: Vec<TokenStream>,
     pub copy_builder_fields: Vec<TokenStream>,
     pub dynamic_block_fields: Vec<TokenStream>,
+    /// `k = type` expressions for the object type constraint, if this is used as a
+    /// variable type.
+    pub variable_type_fields: Vec<TokenStream>,
+    /// Statements inserting each field's literal value into `out`, for
+    /// `FieldValue::to_literal`.
+    pub literal_fields: Vec<TokenStream>,
+    /// Objects with blocks or nested attribute lists/sets (stored in `Vec`s) or
+    /// `dynamic` attributes can't be used as values, so get no type constraint.
+    pub has_non_value_fields: bool,
 }
 
 impl TopLevelFields {
@@ -153,6 +171,27 @@ pub fn generate_field(
     let set_field_name = format_ident!("set_{}", k);
     let set_doc = format!("Set the field `{}`.\n{}", field_name, field_doc);
     let ref_doc = format!("Get a reference to the value of field `{}` after provisioning.\n{}", field_name, field_doc);
+    match behavior {
+        ValueBehaviorHelper::UserRequired => {
+            out
+                .variable_type_fields
+                .push(quote!(format!("{} = {}", #k, < #rust_field_type as FieldValue >:: extract_variable_type())));
+            out.literal_fields.push(quote!(out.insert(#k.into(), self.#field_name.to_literal() ?);));
+        },
+        ValueBehaviorHelper::UserOptional | ValueBehaviorHelper::UserOptionalComputed => {
+            out
+                .variable_type_fields
+                .push(
+                    quote!(
+                        format!("{} = optional({})", #k, < #rust_field_type as FieldValue >:: extract_variable_type())
+                    ),
+                );
+            out.literal_fields.push(quote!(if let Some(v) = &self.#field_name {
+                out.insert(#k.into(), v.to_literal() ?);
+            }));
+        },
+        ValueBehaviorHelper::Computed => { },
+    }
     match behavior {
         ValueBehaviorHelper::UserRequired => {
             out.builder_fields.push(quote!(#[doc = #field_doc] pub #field_name: #rust_field_type));
@@ -238,14 +277,12 @@ pub fn generate_field(
     }
     if let Some((t1, t2)) = rust_field_ref_type {
         if self_has_identity {
-            let ref_fmt = format!("{{}}.{}", k);
             out.ref_methods.push(quote!(#[doc = #ref_doc] pub fn #field_name(&self) -> #t2 {
-                #t1:: new(self.shared().clone(), format!(#ref_fmt, self.extract_ref()))
+                #t1:: new(self.shared().clone(), self.extract_expr().attr(#k))
             }));
         }
-        let ref_ref_fmt = format!("{{}}.{}", k);
         out.ref_ref_methods.push(quote!(#[doc = #ref_doc] pub fn #field_name(&self) -> #t2 {
-            #t1:: new(self.shared().clone(), format!(#ref_ref_fmt, self.base))
+            #t1:: new(self.shared().clone(), self.base.attr(#k))
         }));
     }
 }
@@ -292,7 +329,7 @@ pub fn generate_agg_type_obj(
     at: &AggObjType,
 ) -> (TokenStream, Option<(TokenStream, TokenStream)>) {
     let mut raw_fields = TopLevelFields::default();
-    generate_fields_from_valueschema_map(&mut raw_fields, &path, &at.1, false);
+    generate_fields_from_valueschema_map(&mut raw_fields, path, &at.1, at.2.as_ref(), false);
     let (rust_type, rust_ref_type) = generate_nonident_rust_type(extra_types, path, raw_fields);
     (rust_type, Some((rust_ref_type.clone(), rust_ref_type)))
 }
@@ -339,12 +376,8 @@ fn generate_agg_type_coll(
         AggCollTypeKey::Rec => {
             let (element_type, element_ref_type) = match &at.1 {
                 ValueSchema::Simple(t) => generate_simple_type(&t),
-                ValueSchema::AggColl(_) => {
-                    panic!("supposedly not supported by terraform")
-                },
-                ValueSchema::AggObj(_) => {
-                    panic!("supposedly not supported by terraform")
-                },
+                ValueSchema::AggColl(a) => generate_agg_type_coll(extra_types, &add_path(path, "el"), a.as_ref()),
+                ValueSchema::AggObj(a) => generate_agg_type_obj(extra_types, &add_path(path, "el"), a.as_ref()),
             };
             (
                 quote!(RecField < #element_type >),
@@ -372,6 +405,7 @@ pub fn generate_block_fields(
     self_has_identity: bool,
 ) {
     for (k, v) in fields {
+        out.has_non_value_fields = true;
         let mut path = path.clone();
         path.extend(k.split("_").map(ToString::to_string));
         let rust_type;
@@ -419,6 +453,14 @@ pub fn generate_fields_from_value_map(
     self_has_identity: bool,
 ) {
     for (k, v) in fields {
+        if let Some(n) = &v.nested_type {
+            if !matches!(n.nesting_mode, NestingMode::Single) {
+                out.has_non_value_fields = true;
+            }
+        }
+        if v.r#type.as_ref().map(|t| t.has_dynamic()).unwrap_or(false) {
+            out.has_non_value_fields = true;
+        }
         let mut path = path.clone();
         path.extend(k.split("_").map(ToString::to_string));
         let (rust_field_type, rust_field_ref_type) =
@@ -440,9 +482,13 @@ pub fn generate_fields_from_valueschema_map(
     out: &mut TopLevelFields,
     path: &Vec<String>,
     fields: &BTreeMap<String, ValueSchema>,
+    optional: Option<&Vec<String>>,
     self_has_identity: bool,
 ) {
     for (k, v) in fields {
+        if v.has_dynamic() {
+            out.has_non_value_fields = true;
+        }
         let mut path = path.clone();
         path.extend(k.split("_").map(ToString::to_string));
         let (rust_field_type, rust_field_ref_type) = generate_type(&mut out.extra_types, &path, (Some(v), None));
@@ -452,7 +498,10 @@ pub fn generate_fields_from_valueschema_map(
             rust_field_type,
             rust_field_ref_type,
             "",
-            super::sourceschema::ValueBehaviorHelper::UserOptional,
+            match optional {
+                Some(optional) if !optional.contains(k) => super::sourceschema::ValueBehaviorHelper::UserRequired,
+                _ => super::sourceschema::ValueBehaviorHelper::UserOptional,
+            },
             self_has_identity,
             None,
         );
@@ -475,6 +524,24 @@ pub fn generate_nonident_rust_type(
     let obj_ident = format_ident!("{}", camel_name);
     let obj_builder_ident = format_ident!("Build{}", camel_name);
     let obj_ref_ident = format_ident!("{}Ref", camel_name);
+    if !raw_fields.has_non_value_fields {
+        let variable_type_fields = raw_fields.variable_type_fields;
+        let literal_fields = raw_fields.literal_fields;
+        extra_types.push(quote!{
+            impl FieldValue for #obj_ident {
+                type R = #obj_ref_ident;
+                fn extract_variable_type() -> String {
+                    let fields: Vec<String> = vec![#(#variable_type_fields), *];
+                    format!("object({{{}}})", fields.join(", "))
+                }
+                fn to_literal(&self) -> Result<serde_json::Value, NotLiteral> {
+                    let mut out = serde_json::Map::new();
+                    #(#literal_fields) *
+                    Ok(serde_json::Value::Object(out))
+                }
+            }
+        });
+    }
     extra_types.push(quote!{
         #[derive(Serialize)] pub struct #obj_ident {
             #(#resource_fields,) *
@@ -484,9 +551,19 @@ pub fn generate_nonident_rust_type(
         }
         impl ToListMappable for #obj_ident {
             type O = BlockAssignable < #obj_ident >;
-            fn do_map(self, base: String) -> Self::O {
+            fn do_map(self, base: Expression) -> Self::O {
+                BlockAssignable::Dynamic(DynamicBlock {
+                    for_each: base,
+                    iterator: "each".into(),
+                    content: self,
+                })
+            }
+        }
+        impl RecToListMappable for #obj_ident {
+            type O = BlockAssignable < #obj_ident >;
+            fn do_map(self, base: Expression) -> Self::O {
                 BlockAssignable::Dynamic(DynamicBlock {
-                    for_each: format!("${{{}}}", base),
+                    for_each: base,
                     iterator: "each".into(),
                     content: self,
                 })
@@ -504,16 +581,21 @@ pub fn generate_nonident_rust_type(
         }
         pub struct #obj_ref_ident {
             shared: StackShared,
-            base: String
+            base: Expression
         }
         impl Ref for #obj_ref_ident {
-            fn new(shared: StackShared, base: String) -> #obj_ref_ident {
+            fn new(shared: StackShared, base: Expression) -> #obj_ref_ident {
                 #obj_ref_ident {
                     shared: shared,
-                    base: base.to_string(),
+                    base: base,
                 }
             }
         }
+        impl AttrPath for #obj_ref_ident {
+            fn extract_attr_path(&self) -> Option<(String, String)> {
+                split_attr_path(&self.base)
+            }
+        }
         impl #obj_ref_ident {
             fn shared(&self) -> &StackShared {
                 &self.shared
@@ -523,3 +605,59 @@ pub fn generate_nonident_rust_type(
     });
     (quote!(#obj_ident), quote!(#obj_ref_ident))
 }
+
+/// The `ToArg` kind for a provider function parameter.
+fn generate_function_arg_kind(extra_types: &mut Vec<TokenStream>, path: &Vec<String>, t: &FunctionType) -> TokenStream {
+    match t {
+        FunctionType::Value(ValueSchema::Simple(ScalarTypeKey::Number | ScalarTypeKey::Integer)) => quote!(Number),
+        FunctionType::Value(v) if v.has_dynamic() => quote!(Dynamic),
+        FunctionType::Value(v @ (ValueSchema::Simple(_) | ValueSchema::AggColl(_))) => {
+            generate_type(extra_types, path, (Some(v), None)).1.unwrap().1
+        },
+        FunctionType::Value(ValueSchema::AggObj(_)) | FunctionType::Other(_) => quote!(Dynamic),
+    }
+}
+
+/// Generate a wrapper for provider function `name`, which also declares the
+/// provider as required (with type `provider_type`) when used.
+pub fn generate_function(
+    extra_types: &mut Vec<TokenStream>,
+    shortname: &str,
+    provider_type: &TokenStream,
+    name: &str,
+    function: &Function,
+) -> TokenStream {
+    let path = add_path(&vec!["fn".to_string()], name);
+    let tf_name = format!("provider::{}::{}", shortname, name);
+    let mut generics = vec![];
+    let param = |extra_types: &mut Vec<TokenStream>, p: &FunctionParameter| FunctionParam {
+        ident: format_ident!("{}", sanitize(&p.name).1),
+        kind: generate_function_arg_kind(extra_types, &add_path(&path, &p.name), &p.r#type),
+        name: p.name.clone(),
+        description: p.description.clone(),
+    };
+    let params = function.parameters.iter().map(|p| param(extra_types, p)).collect();
+    let variadic = function.variadic_parameter.as_ref().map(|p| param(extra_types, p));
+    let ret = match &function.return_type {
+        FunctionType::Value(v) if !v.has_dynamic() => {
+            generate_type(extra_types, &add_path(&path, "result"), (Some(v), None)).1.unwrap().1
+        },
+        FunctionType::Value(_) | FunctionType::Other(_) => {
+            generics.push(quote!(R: Ref));
+            quote!(R)
+        },
+    };
+    generate_function_wrapper(FunctionWrapper {
+        fn_ident: format_ident!("tf_{}", name),
+        description: function
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("Generates a call to provider function `{}`.", tf_name)),
+        tf_name,
+        generics,
+        params,
+        variadic,
+        ret,
+        prelude: quote!(stack.shared.add_provider_type(#shortname, #provider_type);),
+    })
+}
@@ -1,3 +1,5 @@
 pub mod errextra;
+pub mod function;
 pub mod generate;
 pub mod sourceschema;
+pub mod modulesource;
@@ -0,0 +1,267 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+};
+use hcl::{
+    Expression,
+    ObjectKey,
+};
+use loga::{
+    ea,
+    ResultContext,
+};
+use super::sourceschema::{
+    AggCollType,
+    AggCollTypeKey,
+    AggObjType,
+    AggObjTypeKey,
+    ScalarTypeKey,
+    Value,
+    ValueSchema,
+};
+
+/// The inputs and outputs of a module, in the same form as provider schema
+/// attributes.
+pub struct ModuleSchema {
+    pub variables: BTreeMap<String, Value>,
+    pub outputs: BTreeMap<String, Value>,
+}
+
+fn fallback_type(context: &str) -> ValueSchema {
+    println!("Type of {} isn't supported, using any", context);
+    ValueSchema::Simple(ScalarTypeKey::Dynamic)
+}
+
+/// Convert a Terraform type constraint expression (ex: `list(object({a =
+/// string}))`) into a schema value type.
+pub fn parse_type(context: &str, e: &Expression) -> ValueSchema {
+    match e {
+        Expression::Variable(v) => match v.as_str() {
+            "string" => ValueSchema::Simple(ScalarTypeKey::String),
+            "number" => ValueSchema::Simple(ScalarTypeKey::Number),
+            "bool" => ValueSchema::Simple(ScalarTypeKey::Bool),
+            "any" => ValueSchema::Simple(ScalarTypeKey::Dynamic),
+            _ => fallback_type(context),
+        },
+        Expression::FuncCall(f) if !f.args.is_empty() => {
+            let coll = |k| ValueSchema::AggColl(Box::new(AggCollType(k, parse_type(context, &f.args[0]))));
+            match f.name.name.as_str() {
+                "list" => coll(AggCollTypeKey::List),
+                "set" => coll(AggCollTypeKey::Set),
+                "map" => coll(AggCollTypeKey::Rec),
+                "object" => {
+                    let Expression::Object(o) = &f.args[0] else {
+                        return fallback_type(context);
+                    };
+                    let mut fields = BTreeMap::new();
+                    let mut optional = vec![];
+                    for (k, v) in o {
+                        let k = match k {
+                            ObjectKey::Identifier(k) => k.to_string(),
+                            ObjectKey::Expression(Expression::Variable(k)) => k.to_string(),
+                            ObjectKey::Expression(Expression::String(k)) => k.clone(),
+                            _ => return fallback_type(context),
+                        };
+                        let field_context = format!("{}.{}", context, k);
+                        let field_type = match v {
+                            Expression::FuncCall(f) if f.name.name.as_str() == "optional" && !f.args.is_empty() => {
+                                optional.push(k.clone());
+                                parse_type(&field_context, &f.args[0])
+                            },
+                            v => parse_type(&field_context, v),
+                        };
+                        fields.insert(k, field_type);
+                    }
+                    ValueSchema::AggObj(Box::new(AggObjType(AggObjTypeKey::Object, fields, Some(optional))))
+                },
+                _ => fallback_type(context),
+            }
+        },
+        _ => fallback_type(context),
+    }
+}
+
+fn new_value(t: ValueSchema, description: Option<String>, required: bool, optional: bool, computed: bool) -> Value {
+    Value {
+        r#type: Some(t),
+        nested_type: None,
+        description,
+        description_kind: None,
+        required,
+        optional,
+        computed,
+        sensitive: false,
+    }
+}
+
+/// Read the `variable` and `output` blocks from the `.tf` files in a module
+/// directory. Outputs don't declare types, so their types come from
+/// `output_types` (type constraint strings), defaulting to `string`.
+pub fn read_module(dir: &Path, output_types: &BTreeMap<String, String>) -> Result<ModuleSchema, loga::Error> {
+    let mut out = ModuleSchema {
+        variables: BTreeMap::new(),
+        outputs: BTreeMap::new(),
+    };
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir).context_with("Error listing module dir", ea!(path = dir.to_string_lossy()))? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "tf").unwrap_or(false) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    for path in paths {
+        let body =
+            hcl::parse(
+                &fs::read_to_string(&path).context_with("Error reading module file", ea!(path = path.to_string_lossy()))?,
+            ).context_with("Error parsing module file", ea!(path = path.to_string_lossy()))?;
+        for block in body.blocks() {
+            let Some(name) = block.labels().first() else {
+                continue;
+            };
+            let name = name.as_str().to_string();
+            let mut description = None;
+            let mut type_ = None;
+            let mut has_default = false;
+            for attr in block.body().attributes() {
+                match (attr.key(), attr.expr()) {
+                    ("description", Expression::String(s)) => description = Some(s.clone()),
+                    ("type", e) => type_ = Some(e.clone()),
+                    ("default", _) => has_default = true,
+                    _ => { },
+                }
+            }
+            match block.identifier() {
+                "variable" => {
+                    let t = match &type_ {
+                        Some(t) => parse_type(&format!("variable {}", name), t),
+                        None => ValueSchema::Simple(ScalarTypeKey::Dynamic),
+                    };
+                    out.variables.insert(name, new_value(t, description, !has_default, has_default, false));
+                },
+                "output" => {
+                    let t = match output_types.get(&name) {
+                        Some(t) => {
+                            let type_body =
+                                hcl::parse(
+                                    &format!("type = {}", t),
+                                ).context_with("Error parsing output type", ea!(output = name, type_ = t))?;
+                            let type_attr =
+                                type_body
+                                    .attributes()
+                                    .next()
+                                    .context_with("Missing output type", ea!(output = name, type_ = t))?;
+                            parse_type(&format!("output {}", name), type_attr.expr())
+                        },
+                        None => ValueSchema::Simple(ScalarTypeKey::String),
+                    };
+                    out.outputs.insert(name, new_value(t, description, false, false, true));
+                },
+                _ => { },
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        path::Path,
+    };
+    use super::{
+        parse_type,
+        read_module,
+        AggCollTypeKey,
+        ScalarTypeKey,
+        ValueSchema,
+    };
+
+    /// Render back to a type constraint, marking optional object attributes.
+    fn show(t: &ValueSchema) -> String {
+        match t {
+            ValueSchema::Simple(t) => match t {
+                ScalarTypeKey::Number | ScalarTypeKey::Integer => "number".into(),
+                ScalarTypeKey::String => "string".into(),
+                ScalarTypeKey::Bool => "bool".into(),
+                ScalarTypeKey::Dynamic => "any".into(),
+            },
+            ValueSchema::AggColl(c) => format!("{}({})", match c.0 {
+                AggCollTypeKey::List => "list",
+                AggCollTypeKey::Set => "set",
+                AggCollTypeKey::Rec => "map",
+            }, show(&c.1)),
+            ValueSchema::AggObj(o) => format!(
+                "object({{{}}})",
+                o
+                    .1
+                    .iter()
+                    .map(|(k, v)| match &o.2 {
+                        Some(optional) if !optional.contains(k) => format!("{} = {}", k, show(v)),
+                        _ => format!("{} = optional({})", k, show(v)),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn parse(t: &str) -> String {
+        let body = hcl::parse(&format!("type = {}", t)).unwrap();
+        show(&parse_type("test", body.attributes().next().unwrap().expr()))
+    }
+
+    #[test]
+    fn parse_simple() {
+        assert_eq!(parse("string"), "string");
+        assert_eq!(parse("list(number)"), "list(number)");
+        assert_eq!(parse("map(set(bool))"), "map(set(bool))");
+        assert_eq!(parse("any"), "any");
+    }
+
+    #[test]
+    fn parse_object() {
+        assert_eq!(
+            parse("object({a = string, b = optional(number, 3), c = object({d = optional(list(string))})})"),
+            "object({a = string, b = optional(number), c = object({d = optional(list(string))})})"
+        );
+    }
+
+    #[test]
+    fn parse_unsupported() {
+        assert_eq!(parse("tuple([string, number])"), "any");
+        assert_eq!(parse("list(tuple([string]))"), "list(any)");
+    }
+
+    #[test]
+    fn read() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bin/generatelib/testdata/module");
+        let module = read_module(&dir, &[("ids".to_string(), "list(string)".to_string())].into_iter().collect()).unwrap();
+        let vars =
+            module
+                .variables
+                .iter()
+                .map(|(k, v)| (k.as_str(), (show(v.r#type.as_ref().unwrap()), v.required, v.description.clone())))
+                .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            vars,
+            [
+                ("anything", ("any".to_string(), true, None)),
+                ("name", ("string".to_string(), true, Some("The name".to_string()))),
+                ("settings", ("object({size = number, tags = optional(map(string))})".to_string(), false, None)),
+            ].into_iter().collect()
+        );
+        let outputs =
+            module
+                .outputs
+                .iter()
+                .map(|(k, v)| (k.as_str(), (show(v.r#type.as_ref().unwrap()), v.computed)))
+                .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            outputs,
+            [("id", ("string".to_string(), true)), ("ids", ("list(string)".to_string(), true))].into_iter().collect()
+        );
+    }
+}
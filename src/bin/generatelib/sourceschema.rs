@@ -14,6 +14,8 @@ pub struct ProviderSchema {
     pub data_source_schemas: BTreeMap<String, SchemaItem>,
     #[serde(default)]
     pub resource_schemas: BTreeMap<String, SchemaItem>,
+    #[serde(default)]
+    pub functions: BTreeMap<String, Function>,
 }
 
 #[derive(Deserialize)]
@@ -41,6 +43,7 @@ pub enum ScalarTypeKey {
     Integer,
     String,
     Bool,
+    Dynamic,
 }
 
 #[derive(Deserialize)]
@@ -61,8 +64,16 @@ pub enum AggObjTypeKey {
     Object,
 }
 
+/// An object type: the attribute types and, if specified, which attributes are
+/// optional. Without the list of optional attributes, all attributes are treated
+/// as optional.
 #[derive(Deserialize)]
-pub struct AggObjType(pub AggObjTypeKey, pub BTreeMap<String, ValueSchema>);
+pub struct AggObjType(
+    pub AggObjTypeKey,
+    pub BTreeMap<String, ValueSchema>,
+    #[serde(default)]
+    pub Option<Vec<String>>,
+);
 
 #[derive(Deserialize)]
 #[serde(untagged)]
@@ -72,6 +83,17 @@ pub enum ValueSchema {
     AggObj(Box<AggObjType>),
 }
 
+impl ValueSchema {
+    /// Whether the type is or contains `dynamic`, which has no reference type.
+    pub fn has_dynamic(&self) -> bool {
+        match self {
+            ValueSchema::Simple(t) => matches!(t, ScalarTypeKey::Dynamic),
+            ValueSchema::AggColl(c) => c.1.has_dynamic(),
+            ValueSchema::AggObj(o) => o.1.values().any(|v| v.has_dynamic()),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DescriptionKind {
@@ -137,3 +159,28 @@ pub enum NestingMode {
     Set,
     Single,
 }
+
+#[derive(Deserialize)]
+pub struct Function {
+    pub description: Option<String>,
+    pub return_type: FunctionType,
+    #[serde(default)]
+    pub parameters: Vec<FunctionParameter>,
+    pub variadic_parameter: Option<FunctionParameter>,
+}
+
+#[derive(Deserialize)]
+pub struct FunctionParameter {
+    pub name: String,
+    pub description: Option<String>,
+    pub r#type: FunctionType,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum FunctionType {
+    Value(ValueSchema),
+    // `dynamic` and anything else without a fixed type (tuples, objects with
+    // optional attributes, etc.)
+    Other(serde_json::Value),
+}
@@ -0,0 +1,24 @@
+variable "name" {
+  type        = string
+  description = "The name"
+}
+
+variable "settings" {
+  type = object({
+    size = number
+    tags = optional(map(string), {})
+  })
+  default = {
+    size = 1
+  }
+}
+
+variable "anything" {}
+
+output "id" {
+  value = "x"
+}
+
+output "ids" {
+  value = ["x"]
+}
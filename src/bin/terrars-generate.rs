@@ -23,7 +23,10 @@ use serde::{
 };
 use serde_json::json;
 use std::{
-    collections::HashSet,
+    collections::{
+        BTreeMap,
+        HashSet,
+    },
     fs::{
         self,
         create_dir_all,
@@ -44,12 +47,60 @@ use crate::generatelib::{
         TopLevelFields,
         generate_fields_from_value_map,
         generate_block_fields,
+        generate_function,
     },
+    modulesource::read_module,
     sourceschema::ProviderSchemas,
 };
 
 pub mod generatelib;
 
+fn write_file(path: &Path, contents: Vec<TokenStream>) -> Result<(), loga::Error> {
+    es!({
+        File::create(path)
+            .context("Failed to create rust file")?
+            .write_all(
+                genemichaels_lib::format_ast(
+                    syn::parse2::<syn::File>(
+                        quote!(#(#contents) *),
+                    ).context_with(
+                        "Failed to parse generated code AST for formatting",
+                        ea!(
+                            context =
+                                contents
+                                    .iter()
+                                    .map(|s| s.to_string())
+                                    .collect::<Vec<String>>()
+                                    .join("\n")
+                                    .lines()
+                                    .enumerate()
+                                    .map(|(ln, l)| format!("{:0>4} {}", ln + 1, l))
+                                    .collect::<Vec<String>>()
+                                    .join("\n")
+                        ),
+                    )?,
+                    &genemichaels_lib::FormatConfig::default(),
+                    Default::default(),
+                )
+                    .map_err(|e| loga::err_with("Error formatting generated code", ea!(err = e)))?
+                    .rendered
+                    .as_bytes(),
+            )
+            .context("Failed to write rust file")?;
+        Ok(())
+    }).context_with("Failed to write generate code", ea!(path = path.to_string_lossy()))?;
+    Ok(())
+}
+
+fn rustfile_template() -> Vec<TokenStream> {
+    vec![quote!(
+        use serde::Serialize;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use terrars::*;
+    )]
+}
+
 pub trait CollCommand {
     fn run(&mut self) -> Result<(), loga::Error>;
 }
@@ -70,18 +121,194 @@ impl CollCommand for Command {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct ModuleConfig {
+    /// Directory containing the module's `.tf` files.
+    module: PathBuf,
+    /// Name used for the generated types, defaults to the directory name.
+    name: Option<String>,
+    /// Module `source` used in the generated stack, defaults to `module`.
+    source: Option<String>,
+    version: Option<String>,
+    /// Type constraints for module outputs, which default to `string`.
+    output_types: Option<BTreeMap<String, String>>,
+    dest: PathBuf,
+}
+
+fn generate_module(config: ModuleConfig) -> Result<(), loga::Error> {
+    let schema = read_module(&config.module, &config.output_types.unwrap_or_default())?;
+    let name = match &config.name {
+        Some(n) => n.clone(),
+        None => config
+            .module
+            .canonicalize()?
+            .file_name()
+            .context_with("Module path has no directory name", ea!(path = config.module.to_string_lossy()))?
+            .to_string_lossy()
+            .to_string(),
+    };
+    let source = config.source.unwrap_or_else(|| config.module.to_string_lossy().to_string());
+    let version = match &config.version {
+        Some(v) => quote!(Some(#v.into())),
+        None => quote!(None),
+    };
+    println!("Generating module {}", name);
+    let name_parts = name.split(&['-', '_']).map(ToString::to_string).collect::<Vec<String>>();
+    let camel_name = to_camel(&name_parts);
+    let mut raw_fields = TopLevelFields::default();
+    generate_fields_from_value_map(&mut raw_fields, &name_parts, &schema.variables, true);
+
+    // Variables can't be read back from the module, only outputs
+    raw_fields.ref_methods.clear();
+    generate_fields_from_value_map(&mut raw_fields, &name_parts, &schema.outputs, true);
+    raw_fields.finish(&camel_name);
+    let builder_fields = raw_fields.builder_fields;
+    let copy_builder_fields = raw_fields.copy_builder_fields;
+    let extra_types = raw_fields.extra_types;
+    let module_fields = raw_fields.fields;
+    let module_mut_methods = raw_fields.mut_methods;
+    let module_ref_methods = raw_fields.ref_methods;
+    let module_ident = format_ident!("{}", camel_name);
+    let module_inner_ident = format_ident!("{}_", camel_name);
+    let module_inner_mut_ident = format_ident!("{}Data", camel_name);
+    let module_builder_ident = format_ident!("Build{}", camel_name);
+    let module_ref_ident = format_ident!("{}Ref", camel_name);
+    let mut out = rustfile_template();
+    out.push(quote!{
+        #[derive(Serialize)] struct #module_inner_mut_ident {
+            source: String,
+            #[serde(skip_serializing_if = "Option::is_none")] version: Option < String >,
+            #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")] providers: std:: collections:: BTreeMap < String,
+            String >,
+            #[serde(skip_serializing_if = "Vec::is_empty")] depends_on: Vec < String >,
+            #[serde(skip_serializing_if = "Option::is_none")] count: Option < PrimField < i64 >>,
+            #[serde(skip_serializing_if = "Option::is_none")] for_each: Option < Expression >,
+            #(#module_fields,) *
+        }
+        struct #module_inner_ident {
+            shared: StackShared,
+            tf_id: String,
+            data: RefCell < #module_inner_mut_ident >,
+        }
+        #[derive(Clone)] pub struct #module_ident(Rc < #module_inner_ident >);
+        impl #module_ident {
+            fn shared(&self) -> &StackShared {
+                &self.0.shared
+            }
+            fn extract_expr(&self) -> Expression {
+                Expression::Ref(self.extract_ref())
+            }
+            pub fn depends_on(self, dep: &impl Referable) -> Self {
+                self.0.data.borrow_mut().depends_on.push(dep.extract_ref());
+                self
+            }
+            pub fn set_provider(self, k: impl ToString, provider_ref: impl ToString) -> Self {
+                self.0.data.borrow_mut().providers.insert(k.to_string(), provider_ref.to_string());
+                self
+            }
+            pub fn set_count(self, v: impl Into < PrimField < i64 >>) -> Self {
+                self.0.data.borrow_mut().count = Some(v.into());
+                self
+            }
+            pub fn instances(&self) -> ListRef < #module_ref_ident > {
+                ListRef::new(self.0.shared.clone(), self.extract_expr())
+            }
+            #(#module_mut_methods) * #(#module_ref_methods) *
+        }
+        impl Referable for #module_ident {
+            fn extract_ref(&self) -> String {
+                format!("module.{}", self.0.tf_id)
+            }
+        }
+        impl RecToListMappable for #module_ident {
+            type O = RecRef < #module_ref_ident >;
+            fn do_map(self, base: Expression) -> Self::O {
+                self.0.data.borrow_mut().for_each = Some(base);
+                RecRef::new(self.0.shared.clone(), self.extract_expr())
+            }
+        }
+        impl Module_ for #module_inner_ident {
+            fn extract_tf_id(&self) -> String {
+                self.tf_id.clone()
+            }
+            fn extract_value(&self) -> serde_json::Value {
+                serde_json::to_value(&self.data).unwrap()
+            }
+        }
+        pub struct #module_builder_ident {
+            pub tf_id: String,
+            #(#builder_fields,) *
+        }
+        impl #module_builder_ident {
+            pub fn build(self, stack:& mut Stack) -> #module_ident {
+                let out = #module_ident(Rc:: new(#module_inner_ident {
+                    shared: stack.shared.clone(),
+                    tf_id: self.tf_id,
+                    data: RefCell:: new(#module_inner_mut_ident {
+                        source: #source.into(),
+                        version: #version,
+                        providers: core:: default:: Default:: default(),
+                        depends_on: core:: default:: Default:: default(),
+                        count: None,
+                        for_each: None,
+                        #(#copy_builder_fields,) *
+                    }),
+                }));
+                stack.add_module(out.0.clone());
+                out
+            }
+        }
+        pub struct #module_ref_ident {
+            shared: StackShared,
+            base: Expression
+        }
+        impl Ref for #module_ref_ident {
+            fn new(shared: StackShared, base: Expression) -> Self {
+                Self {
+                    shared: shared,
+                    base: base,
+                }
+            }
+        }
+        impl #module_ref_ident {
+            fn extract_expr(&self) -> Expression {
+                self.base.clone()
+            }
+            fn shared(&self) -> &StackShared {
+                &self.shared
+            }
+            #(#module_ref_methods) *
+        }
+        #(#extra_types) *
+    });
+    if config.dest.exists() {
+        remove_dir_all(&config.dest)?;
+    }
+    create_dir_all(&config.dest)?;
+    write_file(&config.dest.join("mod.rs"), out)?;
+    Ok(())
+}
+
 fn main() {
     match es!({
         #[derive(Serialize, Deserialize)]
-        struct Config {
+        struct ProviderConfig {
             provider: String,
             version: String,
+            version_constraint: Option<String>,
             include: Option<Vec<String>>,
             exclude: Option<Vec<String>>,
             dest: PathBuf,
             feature_gate: Option<PathBuf>,
         }
 
+        #[derive(Serialize, Deserialize)]
+        #[serde(untagged)]
+        enum Config {
+            Provider(ProviderConfig),
+            Module(ModuleConfig),
+        }
+
         #[derive(Aargvark)]
         struct Arguments {
             /// Path to terrars config jsons.
@@ -95,7 +322,13 @@ fn main() {
             return Err(loga::err("No configs specified; nothing to do"));
         }
         for config in args.configs {
-            let config = config.value;
+            let config = match config.value {
+                Config::Provider(c) => c,
+                Config::Module(c) => {
+                    generate_module(c)?;
+                    continue;
+                },
+            };
             let (vendor, shortname) =
                 config.provider.split_once("/").unwrap_or_else(|| ("hashicorp".into(), &config.provider));
             let provider_prefix = format!("{}_", shortname);
@@ -136,53 +369,6 @@ fn main() {
             let schema: ProviderSchemas =
                 serde_json::from_slice(&schema_raw).context("Error parsing provider schema json from terraform")?;
 
-            // Generate
-            fn write_file(path: &Path, contents: Vec<TokenStream>) -> Result<(), loga::Error> {
-                es!({
-                    File::create(&path)
-                        .context("Failed to create rust file")?
-                        .write_all(
-                            genemichaels_lib::format_ast(
-                                syn::parse2::<syn::File>(
-                                    quote!(#(#contents) *),
-                                ).context_with(
-                                    "Failed to parse generated code AST for formatting",
-                                    ea!(
-                                        context =
-                                            contents
-                                                .iter()
-                                                .map(|s| s.to_string())
-                                                .collect::<Vec<String>>()
-                                                .join("\n")
-                                                .lines()
-                                                .enumerate()
-                                                .map(|(ln, l)| format!("{:0>4} {}", ln + 1, l))
-                                                .collect::<Vec<String>>()
-                                                .join("\n")
-                                    ),
-                                )?,
-                                &genemichaels_lib::FormatConfig::default(),
-                                Default::default(),
-                            )
-                                .map_err(|e| loga::err_with("Error formatting generated code", ea!(err = e)))?
-                                .rendered
-                                .as_bytes(),
-                        )
-                        .context("Failed to write rust file")?;
-                    Ok(())
-                }).context_with("Failed to write generate code", ea!(path = path.to_string_lossy()))?;
-                Ok(())
-            }
-
-            fn rustfile_template() -> Vec<TokenStream> {
-                vec![quote!(
-                    use serde::Serialize;
-                    use std::cell::RefCell;
-                    use std::rc::Rc;
-                    use terrars::*;
-                )]
-            }
-
             // Provider type + provider
             let provider_schema = {
                 let key = format!("registry.terraform.io/{}/{}", vendor, shortname);
@@ -203,7 +389,7 @@ fn main() {
                 let mut out = rustfile_template();
                 let camel_name = to_camel(provider_name_parts);
                 let source = &config.provider;
-                let version = &config.version;
+                let version = config.version_constraint.as_ref().unwrap_or(&config.version);
                 let provider_inner_mut_ident = format_ident!("Provider{}Data", camel_name);
                 let mut raw_fields = TopLevelFields::default();
                 generate_fields_from_value_map(
@@ -324,7 +510,10 @@ fn main() {
                         #[serde(skip_serializing_if = "Vec::is_empty")] depends_on: Vec < String >,
                         #[serde(skip_serializing_if = "Option::is_none")] provider: Option < String >,
                         #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")] lifecycle: ResourceLifecycle,
-                        #[serde(skip_serializing_if = "Option::is_none")] for_each: Option < String >,
+                        #[serde(skip_serializing_if = "Option::is_none")] for_each: Option < Expression >,
+                        #[serde(skip_serializing_if = "Option::is_none")] count: Option < PrimField < i64 >>,
+                        #[serde(skip_serializing_if = "Option::is_none")] connection: Option < Connection >,
+                        #[serde(skip_serializing_if = "Vec::is_empty")] provisioner: Vec < Provisioner >,
                         #(#resource_fields,) *
                     }
                     struct #resource_inner_ident {
@@ -337,6 +526,9 @@ fn main() {
                         fn shared(&self) -> &StackShared {
                             &self.0.shared
                         }
+                        fn extract_expr(&self) -> Expression {
+                            Expression::Ref(self.extract_ref())
+                        }
                         pub fn depends_on(self, dep: &impl Referable) -> Self {
                             self.0.data.borrow_mut().depends_on.push(dep.extract_ref());
                             self
@@ -358,7 +550,26 @@ fn main() {
                                 Some(IgnoreChanges::All(IgnoreChangesAll::All));
                             self
                         }
-                        pub fn ignore_changes_to_attr(self, attr: impl ToString) -> Self {
+                        #[doc = "Ignore changes to the attribute returned by `attr`, ex: `|a| a.tags()`. `attr` gets a reference to this resource's attributes. Serializing the stack fails if `attr` returns anything else."]
+                        pub fn ignore_changes_to_attr<A: AttrPath>(self, attr: impl FnOnce(#resource_ref_ident) -> A) -> Self {
+                            let attr =
+                                match attr(
+                                    #resource_ref_ident::new(self.0.shared.clone(), Expression::Ref("".into())),
+                                ).extract_attr_path() {
+                                    Some((root, path)) if root.is_empty() => path.strip_prefix(".").unwrap_or(&path).to_string(),
+                                    _ => {
+                                        self
+                                            .0
+                                            .data
+                                            .borrow_mut()
+                                            .lifecycle
+                                            .attr_error
+                                            .get_or_insert_with(
+                                                || "`ignore_changes_to_attr` must return an attribute of this resource".into(),
+                                            );
+                                        return self;
+                                    },
+                                };
                             {
                                 let mut d = self.0.data.borrow_mut();
                                 if match &mut d.lifecycle.ignore_changes {
@@ -367,13 +578,13 @@ fn main() {
                                             true
                                         },
                                         IgnoreChanges::Refs(r) => {
-                                            r.push(attr.to_string());
+                                            r.push(attr.clone());
                                             false
                                         },
                                     },
                                     None => true,
                                 } {
-                                    d.lifecycle.ignore_changes = Some(IgnoreChanges::Refs(vec![attr.to_string()]));
+                                    d.lifecycle.ignore_changes = Some(IgnoreChanges::Refs(vec![attr]));
                                 }
                             }
                             self
@@ -382,8 +593,77 @@ fn main() {
                             self.0.data.borrow_mut().lifecycle.replace_triggered_by.push(r.extract_ref());
                             self
                         }
-                        pub fn replace_triggered_by_attr(self, attr: impl ToString) -> Self {
-                            self.0.data.borrow_mut().lifecycle.replace_triggered_by.push(attr.to_string());
+                        #[doc = "Replace this resource when the attribute of another resource `attr` changes. Serializing the stack fails if `attr` isn't an attribute reference."]
+                        pub fn replace_triggered_by_attr(self, attr: &impl AttrPath) -> Self {
+                            let attr = match attr.extract_attr_path() {
+                                Some((root, path)) if !root.is_empty() => format!("{}{}", root, path),
+                                _ => {
+                                    self
+                                        .0
+                                        .data
+                                        .borrow_mut()
+                                        .lifecycle
+                                        .attr_error
+                                        .get_or_insert_with(
+                                            || "`replace_triggered_by_attr` must get an attribute of another resource".into(),
+                                        );
+                                    return self;
+                                },
+                            };
+                            self.0.data.borrow_mut().lifecycle.replace_triggered_by.push(attr);
+                            self
+                        }
+                        #[doc = "Import existing infrastructure with the provider-specific id `id` into this resource."]
+                        pub fn import_from(self, id: impl Into < PrimField < String >>) -> Self {
+                            self.0.shared.add_import(self.extract_ref(), id.into());
+                            self
+                        }
+                        #[doc = "Set the default connection for `remote-exec` and `file` provisioners."]
+                        pub fn set_connection(self, v: Connection) -> Self {
+                            self.0.data.borrow_mut().connection = Some(v);
+                            self
+                        }
+                        #[doc = "Add a provisioner, run after creation or before destruction. Provisioners run in the order they're added."]
+                        pub fn add_provisioner(self, v: impl Into < Provisioner >) -> Self {
+                            self.0.data.borrow_mut().provisioner.push(v.into());
+                            self
+                        }
+                        #[doc = "Get a reference to this resource as `self`, only valid within its own provisioners, connection, and postconditions."]
+                        pub fn self_ref(&self) -> #resource_ref_ident {
+                            #resource_ref_ident::new(self.0.shared.clone(), Expression::Ref("self".into()))
+                        }
+                        #[doc = "Create `v` instances of this, ex: `0` or `1` to make it conditional. Use `Stack::count_index` to vary each instance's fields and `instances` to refer to the instances."]
+                        pub fn set_count(self, v: impl Into < PrimField < i64 >>) -> Self {
+                            self.0.data.borrow_mut().count = Some(v.into());
+                            self
+                        }
+                        #[doc = "Get a reference to the instances created with `set_count`."]
+                        pub fn instances(&self) -> ListRef < #resource_ref_ident > {
+                            ListRef::new(self.0.shared.clone(), self.extract_expr())
+                        }
+                        #[doc = "Fail with `error_message` before planning changes to the resource if `condition` is false."]
+                        pub fn add_precondition(
+                            self,
+                            condition: impl Into < PrimField < bool >>,
+                            error_message: impl Into < PrimField < String >>
+                        ) -> Self {
+                            self.0.data.borrow_mut().lifecycle.precondition.push(Condition {
+                                condition: condition.into(),
+                                error_message: error_message.into(),
+                            });
+                            self
+                        }
+                        #[doc = "Fail with `error_message` after planning changes to the resource if the condition returned by `condition` is false. `condition` gets a reference to this as `self`."]
+                        pub fn add_postcondition(
+                            self,
+                            condition: impl FnOnce(#resource_ref_ident) -> PrimExpr < bool >,
+                            error_message: impl Into < PrimField < String >>
+                        ) -> Self {
+                            let condition = condition(#resource_ref_ident::new(self.0.shared.clone(), Expression::Ref("self".into())));
+                            self.0.data.borrow_mut().lifecycle.postcondition.push(Condition {
+                                condition: condition.into(),
+                                error_message: error_message.into(),
+                            });
                             self
                         }
                         #(#resource_mut_methods) * #(#resource_ref_methods) *
@@ -395,11 +675,16 @@ fn main() {
                     }
                     impl Resource for #resource_ident {
                     }
-                    impl ToListMappable for #resource_ident {
-                        type O = ListRef < #resource_ref_ident >;
-                        fn do_map(self, base: String) -> Self::O {
-                            self.0.data.borrow_mut().for_each = Some(format!("${{{}}}", base));
-                            ListRef::new(self.0.shared.clone(), self.extract_ref())
+                    impl ResourceType for #resource_ident {
+                        fn resource_type() -> &'static str {
+                            #resource_name
+                        }
+                    }
+                    impl RecToListMappable for #resource_ident {
+                        type O = RecRef < #resource_ref_ident >;
+                        fn do_map(self, base: Expression) -> Self::O {
+                            self.0.data.borrow_mut().for_each = Some(base);
+                            RecRef::new(self.0.shared.clone(), self.extract_expr())
                         }
                     }
                     impl Resource_ for #resource_inner_ident {
@@ -409,8 +694,12 @@ fn main() {
                         fn extract_tf_id(&self) -> String {
                             self.tf_id.clone()
                         }
-                        fn extract_value(&self) -> serde_json::Value {
-                            serde_json::to_value(&self.data).unwrap()
+                        fn extract_value(&self) -> Result<serde_json::Value, StackError> {
+                            let data = self.data.borrow();
+                            if let Some(e) = &data.lifecycle.attr_error {
+                                return Err(StackError::LifecycleAttr(format!("{}.{}", #resource_name, self.tf_id), e.clone()));
+                            }
+                            Ok(serde_json::to_value(&*data).unwrap())
                         }
                     }
                     pub struct #resource_builder_ident {
@@ -427,6 +716,9 @@ fn main() {
                                     provider: None,
                                     lifecycle: core:: default:: Default:: default(),
                                     for_each: None,
+                                    count: None,
+                                    connection: None,
+                                    provisioner: core:: default:: Default:: default(),
                                     #(#copy_builder_fields,) *
                                 }),
                             }));
@@ -436,18 +728,23 @@ fn main() {
                     }
                     pub struct #resource_ref_ident {
                         shared: StackShared,
-                        base: String
+                        base: Expression
                     }
                     impl Ref for #resource_ref_ident {
-                        fn new(shared: StackShared, base: String) -> Self {
+                        fn new(shared: StackShared, base: Expression) -> Self {
                             Self {
                                 shared: shared,
                                 base: base,
                             }
                         }
                     }
+                    impl AttrPath for #resource_ref_ident {
+                        fn extract_attr_path(&self) -> Option<(String, String)> {
+                            split_attr_path(&self.base)
+                        }
+                    }
                     impl #resource_ref_ident {
-                        fn extract_ref(&self) -> String {
+                        fn extract_expr(&self) -> Expression {
                             self.base.clone()
                         }
                         fn shared(&self) -> &StackShared {
@@ -514,7 +811,9 @@ fn main() {
                     #[derive(Serialize)] struct #datasource_inner_mut_ident {
                         #[serde(skip_serializing_if = "Vec::is_empty")] depends_on: Vec < String >,
                         #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")] provider: Option < String >,
-                        #[serde(skip_serializing_if = "Option::is_none")] for_each: Option < String >,
+                        #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")] lifecycle: DatasourceLifecycle,
+                        #[serde(skip_serializing_if = "Option::is_none")] for_each: Option < Expression >,
+                        #[serde(skip_serializing_if = "Option::is_none")] count: Option < PrimField < i64 >>,
                         #(#datasource_fields,) *
                     }
                     struct #datasource_inner_ident {
@@ -527,6 +826,9 @@ fn main() {
                         fn shared(&self) -> &StackShared {
                             &self.0.shared
                         }
+                        fn extract_expr(&self) -> Expression {
+                            Expression::Ref(self.extract_ref())
+                        }
                         pub fn depends_on(self, dep: &impl Referable) -> Self {
                             self.0.data.borrow_mut().depends_on.push(dep.extract_ref());
                             self
@@ -535,6 +837,40 @@ fn main() {
                             self.0.data.borrow_mut().provider = Some(provider.provider_ref());
                             self
                         }
+                        #[doc = "Create `v` instances of this, ex: `0` or `1` to make it conditional. Use `Stack::count_index` to vary each instance's fields and `instances` to refer to the instances."]
+                        pub fn set_count(self, v: impl Into < PrimField < i64 >>) -> Self {
+                            self.0.data.borrow_mut().count = Some(v.into());
+                            self
+                        }
+                        #[doc = "Get a reference to the instances created with `set_count`."]
+                        pub fn instances(&self) -> ListRef < #datasource_ref_ident > {
+                            ListRef::new(self.0.shared.clone(), self.extract_expr())
+                        }
+                        #[doc = "Fail with `error_message` before reading the datasource if `condition` is false."]
+                        pub fn add_precondition(
+                            self,
+                            condition: impl Into < PrimField < bool >>,
+                            error_message: impl Into < PrimField < String >>
+                        ) -> Self {
+                            self.0.data.borrow_mut().lifecycle.precondition.push(Condition {
+                                condition: condition.into(),
+                                error_message: error_message.into(),
+                            });
+                            self
+                        }
+                        #[doc = "Fail with `error_message` after reading the datasource if the condition returned by `condition` is false. `condition` gets a reference to this as `self`."]
+                        pub fn add_postcondition(
+                            self,
+                            condition: impl FnOnce(#datasource_ref_ident) -> PrimExpr < bool >,
+                            error_message: impl Into < PrimField < String >>
+                        ) -> Self {
+                            let condition = condition(#datasource_ref_ident::new(self.0.shared.clone(), Expression::Ref("self".into())));
+                            self.0.data.borrow_mut().lifecycle.postcondition.push(Condition {
+                                condition: condition.into(),
+                                error_message: error_message.into(),
+                            });
+                            self
+                        }
                         #(#datasource_mut_methods) * #(#datasource_ref_methods) *
                     }
                     impl Referable for #datasource_ident {
@@ -544,11 +880,11 @@ fn main() {
                     }
                     impl Datasource for #datasource_ident {
                     }
-                    impl ToListMappable for #datasource_ident {
-                        type O = ListRef < #datasource_ref_ident >;
-                        fn do_map(self, base: String) -> Self::O {
-                            self.0.data.borrow_mut().for_each = Some(format!("${{{}}}", base));
-                            ListRef::new(self.0.shared.clone(), self.extract_ref())
+                    impl RecToListMappable for #datasource_ident {
+                        type O = RecRef < #datasource_ref_ident >;
+                        fn do_map(self, base: Expression) -> Self::O {
+                            self.0.data.borrow_mut().for_each = Some(base);
+                            RecRef::new(self.0.shared.clone(), self.extract_expr())
                         }
                     }
                     impl Datasource_ for #datasource_inner_ident {
@@ -574,7 +910,9 @@ fn main() {
                                 data: RefCell:: new(#datasource_inner_mut_ident {
                                     depends_on: core:: default:: Default:: default(),
                                     provider: None,
+                                    lifecycle: core:: default:: Default:: default(),
                                     for_each: None,
+                                    count: None,
                                     #(#copy_builder_fields,) *
                                 }),
                             }));
@@ -584,10 +922,10 @@ fn main() {
                     }
                     pub struct #datasource_ref_ident {
                         shared: StackShared,
-                        base: String
+                        base: Expression
                     }
                     impl Ref for #datasource_ref_ident {
-                        fn new(shared: StackShared, base: String) -> Self {
+                        fn new(shared: StackShared, base: Expression) -> Self {
                             Self {
                                 shared: shared,
                                 base: base,
@@ -598,7 +936,7 @@ fn main() {
                         fn shared(&self) -> &StackShared {
                             &self.shared
                         }
-                        fn extract_ref(&self) -> String {
+                        fn extract_expr(&self) -> Expression {
                             self.base.clone()
                         }
                         #(#datasource_ref_methods) *
@@ -618,6 +956,25 @@ fn main() {
                     #feature_gate pub use #path_ident::*;
                 });
             }
+
+            // Provider-defined functions
+            if !provider_schema.functions.is_empty() {
+                let mut out = rustfile_template();
+                let source = &config.provider;
+                let version = config.version_constraint.as_ref().unwrap_or(&config.version);
+                let provider_type = quote!(serde_json::json!({
+                    "source": #source,
+                    "version": #version,
+                }));
+                let mut extra_types = vec![];
+                for (function_name, function) in &provider_schema.functions {
+                    println!("Generating function {}", function_name);
+                    out.push(generate_function(&mut extra_types, shortname, &provider_type, function_name, function));
+                }
+                out.extend(extra_types);
+                write_file(&provider_dir.join("functions.rs"), out)?;
+                mod_out.push(quote!(pub mod functions; pub use functions::*;));
+            }
             write_file(&provider_dir.join("mod.rs"), mod_out)?;
             if whitelist && !include.is_empty() {
                 return Err(
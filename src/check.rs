@@ -0,0 +1,89 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+use serde::Serialize;
+use serde_json::Value;
+use crate::{
+    Condition,
+    Datasource,
+    PrimField,
+    Stack,
+};
+
+#[derive(Serialize)]
+struct CheckData<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+    assert: &'a Vec<Condition>,
+}
+
+pub(crate) struct Check_ {
+    tf_id: String,
+    assert: RefCell<Vec<Condition>>,
+    datasource: RefCell<Option<String>>,
+}
+
+impl Check_ {
+    pub(crate) fn extract_tf_id(&self) -> String {
+        self.tf_id.clone()
+    }
+
+    /// The address of the scoped datasource, ex: `data.http.health`.
+    pub(crate) fn extract_datasource(&self) -> Option<String> {
+        self.datasource.borrow().clone()
+    }
+
+    /// `data` is the scoped datasource's value, `{type: {id: value}}`.
+    pub(crate) fn extract_value(&self, data: Option<Value>) -> Value {
+        serde_json::to_value(&CheckData {
+            data,
+            assert: &self.assert.borrow(),
+        }).unwrap()
+    }
+}
+
+/// A `check` block, validating the infrastructure on every plan and apply. Failed
+/// assertions produce warnings rather than stopping the apply.
+pub struct Check(Rc<Check_>);
+
+impl Check {
+    /// Warn with `error_message` if `condition` is false.
+    pub fn add_assert(
+        self,
+        condition: impl Into<PrimField<bool>>,
+        error_message: impl Into<PrimField<String>>,
+    ) -> Self {
+        self.0.assert.borrow_mut().push(Condition {
+            condition: condition.into(),
+            error_message: error_message.into(),
+        });
+        self
+    }
+
+    /// Move a datasource from the stack into this check, so it's only read while
+    /// checking and failures reading it are warnings. It can then only be referred to
+    /// in this check's assertions. Only one datasource can be scoped to a check, and
+    /// the datasource must be in the stack and not scoped to any other check.
+    pub fn set_datasource(self, v: &impl Datasource) -> Self {
+        *self.0.datasource.borrow_mut() = Some(v.extract_ref());
+        self
+    }
+}
+
+/// Create a new check. Add at least one assertion with `add_assert`.
+pub struct BuildCheck {
+    pub tf_id: String,
+}
+
+impl BuildCheck {
+    pub fn build(self, stack: &mut Stack) -> Check {
+        let out = Rc::new(Check_ {
+            tf_id: self.tf_id,
+            assert: RefCell::new(vec![]),
+            datasource: RefCell::new(None),
+        });
+        stack.checks.push(out.clone());
+        Check(out)
+    }
+}
@@ -0,0 +1,39 @@
+use serde::Serialize;
+use crate::{
+    FieldValue,
+    PrimField,
+};
+
+/// A value of any type, for fields with the Terraform type `any` (ex: untyped
+/// module variables). Convert primitives or any other field value with `into()`.
+pub struct DynamicField(Box<dyn erased_serde::Serialize>);
+
+impl Serialize for DynamicField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<V: FieldValue + 'static> From<V> for DynamicField {
+    fn from(value: V) -> Self {
+        DynamicField(Box::new(value))
+    }
+}
+
+macro_rules! prim_dynamic_field{
+    ($t: ty, $p: ty) => {
+        impl From<$t> for DynamicField {
+            fn from(value: $t) -> Self {
+                DynamicField(Box::new(PrimField::<$p>::from(value)))
+            }
+        }
+    };
+}
+
+prim_dynamic_field!(&str, String);
+prim_dynamic_field!(String, String);
+prim_dynamic_field!(bool, bool);
+prim_dynamic_field!(i64, i64);
+prim_dynamic_field!(f64, f64);
@@ -1,82 +1,476 @@
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
 use crate::{
     PrimType,
     StackShared,
 };
 
-// Expressions
-pub trait Expr<T: PrimType> {
-    fn expr_raw(&self) -> (&StackShared, String);
+/// A Terraform expression. Expressions are kept as a tree until the stack is
+/// serialized and are rendered in a single pass, so literal strings are always
+/// escaped exactly once.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expression {
+    /// A literal value: a string, number, bool or null.
+    Literal(Value),
+    /// A reference root, ex: `aws_instance.x`, `var.x`, `each.value`.
+    Ref(String),
+    /// An attribute of another expression, `base.name`.
+    Attr(Box<Expression>, String),
+    /// An element of another expression, `base[index]`.
+    Index(Box<Expression>, Box<Expression>),
+    /// Verbatim expression text from `stack.expr`. This is parenthesized when used
+    /// as an operand.
+    Raw(String),
+    /// A function call, `name(args...)`.
+    Call(String, Vec<Expression>),
+    /// A list literal, `[a, b]`.
+    List(Vec<Expression>),
+    /// An object literal, `{ "k" = v }`.
+    Object(Vec<(String, Expression)>),
+    /// A string template, ex: `"prefix-${var.x}"`.
+    Template(Vec<TemplatePart>),
+    /// A `for` expression, producing a list or (if `key_out` is set) an object.
+    For(Box<ForExpr>),
+    /// `a op b`
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    /// `op a`
+    Unary(UnaryOp, Box<Expression>),
+    /// `cond ? a : b`
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::Ne => 3,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+const PREC_UNARY: u8 = 7;
+const PREC_ATOM: u8 = 8;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TemplatePart {
+    /// Literal text, escaped when rendered.
+    Lit(String),
+    /// An interpolation, `${...}`.
+    Interp(Expression),
+    /// `%{ if cond }...%{ else }...%{ endif }`
+    If(Box<TemplateIf>),
+    /// `%{ for key_var, value_var in collection }...%{ endfor }`
+    For(Box<TemplateFor>),
+}
+
+/// Strip markers (`~`) for template directives, which remove whitespace (including
+/// newlines) immediately before or after each tag of the directive.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Strip {
+    pub before: bool,
+    pub after: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TemplateIf {
+    pub cond: Expression,
+    pub then: Vec<TemplatePart>,
+    pub else_: Vec<TemplatePart>,
+    pub strip: Strip,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TemplateFor {
+    pub key_var: Option<String>,
+    pub value_var: String,
+    pub collection: Expression,
+    pub body: Vec<TemplatePart>,
+    pub strip: Strip,
+}
+
+/// `[for key_var, value_var in collection: value_out]`, or `{for ... : key_out =>
+/// value_out}` if `key_out` is set.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ForExpr {
+    pub key_var: Option<String>,
+    pub value_var: String,
+    pub collection: Expression,
+    pub key_out: Option<Expression>,
+    pub value_out: Expression,
+}
+
+/// Escape text for use in a template, where `${` and `%{` start interpolations and
+/// directives.
+pub(crate) fn escape_template(s: &str) -> String {
+    s.replace("${", "$${").replace("%{", "%%{")
+}
+
+/// Escape text for use in a quoted HCL string.
+fn escape_quoted(s: &str) -> String {
+    escape_template(
+        &s.replace("\\", "\\\\").replace("\"", "\\\"").replace("\n", "\\n").replace("\r", "\\r").replace("\t", "\\t"),
+    )
+}
+
+fn render_directive(strip: Strip, body: &str) -> String {
+    format!("%{{{}{}{}}}", if strip.before {
+        "~ "
+    } else {
+        " "
+    }, body, if strip.after {
+        " ~"
+    } else {
+        " "
+    })
+}
+
+/// Render template contents, escaping literal text with `escape`.
+fn render_template(parts: &[TemplatePart], escape: fn(&str) -> String) -> String {
+    let mut out = String::new();
+    for part in parts {
+        match part {
+            TemplatePart::Lit(s) => out.push_str(&escape(s)),
+            TemplatePart::Interp(e) => out.push_str(&format!("${{{}}}", e.render())),
+            TemplatePart::If(i) => {
+                out.push_str(&render_directive(i.strip, &format!("if {}", i.cond.render())));
+                out.push_str(&render_template(&i.then, escape));
+                if !i.else_.is_empty() {
+                    out.push_str(&render_directive(i.strip, "else"));
+                    out.push_str(&render_template(&i.else_, escape));
+                }
+                out.push_str(&render_directive(i.strip, "endif"));
+            },
+            TemplatePart::For(f) => {
+                let vars = match &f.key_var {
+                    Some(k) => format!("{}, {}", k, f.value_var),
+                    None => f.value_var.clone(),
+                };
+                out.push_str(&render_directive(f.strip, &format!("for {} in {}", vars, f.collection.render())));
+                out.push_str(&render_template(&f.body, escape));
+                out.push_str(&render_directive(f.strip, "endfor"));
+            },
+        }
+    }
+    out
+}
+
+impl Expression {
+    /// `self.name`, ex: an attribute of a resource.
+    pub fn attr(&self, name: impl ToString) -> Expression {
+        Expression::Attr(Box::new(self.clone()), name.to_string())
+    }
+
+    /// `self[index]`, an element of a list or record.
+    pub fn index(&self, index: Expression) -> Expression {
+        Expression::Index(Box::new(self.clone()), Box::new(index))
+    }
+
+    /// Render as HCL expression text.
+    pub fn render(&self) -> String {
+        match self {
+            Expression::Literal(Value::String(s)) => format!("\"{}\"", escape_quoted(s)),
+            Expression::Literal(v) => v.to_string(),
+            Expression::Ref(r) => r.clone(),
+            Expression::Attr(base, name) => format!("{}.{}", base.render_operand(PREC_ATOM), name),
+            Expression::Index(base, index) => format!("{}[{}]", base.render_operand(PREC_ATOM), index.render()),
+            Expression::Raw(r) => r.clone(),
+            Expression::Call(name, args) => {
+                format!("{}({})", name, args.iter().map(|a| a.render()).collect::<Vec<_>>().join(", "))
+            },
+            Expression::List(values) => {
+                format!("[{}]", values.iter().map(|v| v.render()).collect::<Vec<_>>().join(", "))
+            },
+            Expression::Object(values) => {
+                format!(
+                    "{{{}}}",
+                    values
+                        .iter()
+                        .map(|(k, v)| format!("\"{}\" = {}", escape_quoted(k), v.render()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            },
+            Expression::Template(parts) => format!("\"{}\"", render_template(parts, escape_quoted)),
+            Expression::For(f) => {
+                let vars = match &f.key_var {
+                    Some(k) => format!("{}, {}", k, f.value_var),
+                    None => f.value_var.clone(),
+                };
+                match &f.key_out {
+                    Some(k) => format!(
+                        "{{for {} in {}: {} => {}}}",
+                        vars,
+                        f.collection.render(),
+                        k.render(),
+                        f.value_out.render()
+                    ),
+                    None => format!("[for {} in {}: {}]", vars, f.collection.render(), f.value_out.render()),
+                }
+            },
+            Expression::Binary(op, a, b) => {
+                // Operators are left associative; comparisons aren't associative at all
+                let p = op.precedence();
+                let comparison = matches!(p, 3 | 4);
+                format!(
+                    "{} {} {}",
+                    a.render_operand(if comparison {
+                        p + 1
+                    } else {
+                        p
+                    }),
+                    op.symbol(),
+                    b.render_operand(p + 1)
+                )
+            },
+            Expression::Unary(op, a) => format!("{}{}", match op {
+                UnaryOp::Not => "!",
+                UnaryOp::Neg => "-",
+            }, a.render_operand(PREC_UNARY + 1)),
+            Expression::Conditional(c, a, b) => format!(
+                "{} ? {} : {}",
+                c.render_operand(1),
+                a.render_operand(1),
+                b.render_operand(0)
+            ),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Raw(_) | Expression::Conditional(..) => 0,
+            Expression::Binary(op, ..) => op.precedence(),
+            Expression::Unary(..) => PREC_UNARY,
+            Expression::Literal(Value::Number(n)) if n.as_f64().unwrap_or_default() < 0. => PREC_UNARY,
+            _ => PREC_ATOM,
+        }
+    }
 
-    fn expr_sentinel(&self) -> String {
-        let r = self.expr_raw();
-        r.0.add_sentinel(&r.1)
+    /// Render, parenthesizing if the expression binds less tightly than `min`.
+    fn render_operand(&self, min: u8) -> String {
+        if self.precedence() < min {
+            format!("({})", self.render())
+        } else {
+            self.render()
+        }
+    }
+
+    /// Render as the value of a field in the stack json, where strings are
+    /// templates.
+    fn render_field(&self) -> String {
+        match self {
+            Expression::Literal(Value::String(s)) => escape_template(s),
+            Expression::Template(parts) => render_template(parts, escape_template),
+            e => format!("${{{}}}", e.render()),
+        }
     }
 }
 
+/// An expression where only a literal value is allowed (ex: a variable default).
+#[derive(Error, Debug)]
+#[error("Expected a literal value, got expression `{}`", .0.render())]
+pub struct NotLiteral(pub Expression);
+
+impl Serialize for Expression {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        match self {
+            Expression::Literal(v) if !v.is_string() => v.serialize(serializer),
+            e => e.render_field().serialize(serializer),
+        }
+    }
+}
+
+// Expressions
+pub trait Expr<T: PrimType> {
+    fn expr(&self) -> (&StackShared, Expression);
+}
+
 // More crazy rust limitation workarounds
 #[macro_export]
 macro_rules! manual_expr_impls{
     ($t: ident) => {
-        impl < T: PrimType > Into < String > for $t < T > {
-            fn into(self) -> String {
-                self.expr_sentinel()
-            }
-        }
-        impl < T: PrimType > Into < String > for & $t < T > {
-            fn into(self) -> String {
-                self.expr_sentinel()
-            }
-        }
-        impl < T: PrimType > Display for $t < T > {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                self.expr_sentinel().fmt(f)
-            }
-        }
         impl < T: PrimType > Into < PrimField < T >> for $t < T > {
             fn into(self) -> PrimField<T> {
-                PrimField::Sentinel(self.expr_sentinel())
+                PrimField::Expr(self.expr().1)
             }
         }
         impl < T: PrimType > Into < PrimField < T >> for & $t < T > {
             fn into(self) -> PrimField<T> {
-                PrimField::Sentinel(self.expr_sentinel())
+                PrimField::Expr(self.expr().1)
             }
         }
         impl Into < PrimExpr < String >> for $t < bool > {
             fn into(self) -> PrimExpr<String> {
-                let (shared, raw) = self.expr_raw();
-                PrimExpr(shared.clone(), raw, Default::default())
+                let (shared, e) = self.expr();
+                PrimExpr(shared.clone(), e, Default::default())
             }
         }
         impl Into < PrimExpr < String >> for & $t < bool > {
             fn into(self) -> PrimExpr<String> {
-                let (shared, raw) = self.expr_raw();
-                PrimExpr(shared.clone(), raw, Default::default())
+                let (shared, e) = self.expr();
+                PrimExpr(shared.clone(), e, Default::default())
             }
         }
         impl Into < PrimExpr < String >> for $t < i64 > {
             fn into(self) -> PrimExpr<String> {
-                let (shared, raw) = self.expr_raw();
-                PrimExpr(shared.clone(), raw, Default::default())
+                let (shared, e) = self.expr();
+                PrimExpr(shared.clone(), e, Default::default())
             }
         }
         impl Into < PrimExpr < String >> for & $t < i64 > {
             fn into(self) -> PrimExpr<String> {
-                let (shared, raw) = self.expr_raw();
-                PrimExpr(shared.clone(), raw, Default::default())
+                let (shared, e) = self.expr();
+                PrimExpr(shared.clone(), e, Default::default())
             }
         }
         impl Into < PrimExpr < String >> for $t < f64 > {
             fn into(self) -> PrimExpr<String> {
-                let (shared, raw) = self.expr_raw();
-                PrimExpr(shared.clone(), raw, Default::default())
+                let (shared, e) = self.expr();
+                PrimExpr(shared.clone(), e, Default::default())
             }
         }
         impl Into < PrimExpr < String >> for & $t < f64 > {
             fn into(self) -> PrimExpr<String> {
-                let (shared, raw) = self.expr_raw();
-                PrimExpr(shared.clone(), raw, Default::default())
+                let (shared, e) = self.expr();
+                PrimExpr(shared.clone(), e, Default::default())
             }
         }
     };
 }
+
+/// Build `[for each in [for k, v in base: { key = k, value = v }]: value_out]` (or an
+/// object with `key_out`), where `each` matches the iteration variable of
+/// resource-level for-each. `index_var` is `i` for lists or `k` for records.
+pub(crate) fn each_for(
+    base: &Expression,
+    index_var: &str,
+    key_out: Option<Expression>,
+    value_out: Expression,
+) -> Expression {
+    Expression::For(Box::new(ForExpr {
+        key_var: None,
+        value_var: "each".into(),
+        collection: Expression::For(Box::new(ForExpr {
+            key_var: Some(index_var.into()),
+            value_var: "v".into(),
+            collection: base.clone(),
+            key_out: None,
+            value_out: Expression::Object(
+                vec![("key".into(), Expression::Ref(index_var.into())), ("value".into(), Expression::Ref("v".into()))],
+            ),
+        })),
+        key_out,
+        value_out,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::{
+        each_for,
+        Expression,
+        TemplatePart,
+    };
+    use crate::{
+        FieldValue,
+        ListField,
+        PrimField,
+    };
+
+    #[test]
+    fn escape_literal_field() {
+        let e = Expression::Literal(json!("a ${b} %{c} \"d\"\n"));
+        assert_eq!(serde_json::to_value(&e).unwrap(), json!("a $${b} %%{c} \"d\"\n"));
+        assert_eq!(e.render(), "\"a $${b} %%{c} \\\"d\\\"\\n\"");
+    }
+
+    #[test]
+    fn escape_template_parts() {
+        let e =
+            Expression::Template(
+                vec![TemplatePart::Lit("${x}-".into()), TemplatePart::Interp(Expression::Ref("var.y".into()))],
+            );
+        assert_eq!(serde_json::to_value(&e).unwrap(), json!("$${x}-${var.y}"));
+        assert_eq!(
+            Expression::Call("upper".into(), vec![e]).render(),
+            "upper(\"$${x}-${var.y}\")"
+        );
+    }
+
+    #[test]
+    fn non_string_literal_field() {
+        assert_eq!(serde_json::to_value(Expression::Literal(json!(4))).unwrap(), json!(4));
+        assert_eq!(serde_json::to_value(Expression::Ref("var.x".into())).unwrap(), json!("${var.x}"));
+    }
+
+    #[test]
+    fn to_literal() {
+        let v = ListField::from(vec![PrimField::from("${x}")]);
+        assert_eq!(v.to_literal().unwrap(), json!(["${x}"]));
+        assert_eq!(serde_json::to_value(&v).unwrap(), json!(["$${x}"]));
+        let v = ListField::from(vec![PrimField::<String>::Expr(Expression::Ref("var.x".into()))]);
+        assert_eq!(v.to_literal().unwrap_err().to_string(), "Expected a literal value, got expression `var.x`");
+    }
+
+    #[test]
+    fn attr_index() {
+        let e = Expression::Ref("aws_instance.x".into()).attr("tags").index(Expression::Literal(json!("k")));
+        assert_eq!(e.render(), "aws_instance.x.tags[\"k\"]");
+        let e = Expression::Raw("a + b".into()).attr("c");
+        assert_eq!(e.render(), "(a + b).c");
+    }
+
+    #[test]
+    fn each_for_object() {
+        let e = each_for(&Expression::Ref("var.l".into()), "i", None, Expression::Ref("each.value".into()));
+        assert_eq!(e.render(), "[for each in [for i, v in var.l: {\"key\" = i, \"value\" = v}]: each.value]");
+    }
+}
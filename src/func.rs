@@ -1,69 +1,330 @@
+use std::collections::BTreeMap;
 use crate::{
     expr::{
         Expr,
+        Expression,
     },
     prim_ref::PrimExpr,
+    ListRef,
     PrimType,
     PrimField,
+    RecRef,
+    Ref,
+    SetRef,
+    Stack,
     StackShared,
+    TfPrimitiveType,
 };
 
-pub trait ToFuncArg<T: PrimType> {
-    fn to_func_arg(self, shared: &StackShared) -> PrimExpr<T>;
-}
-
-impl<T: PrimType> ToFuncArg<T> for T {
+/// Values usable as `T` operands and arguments. Anything accepted here is also
+/// accepted by the generated function wrappers (`ToArg<PrimExpr<T>>`), so code
+/// generic over `ToFuncArg` can call them.
+pub trait ToFuncArg<T: PrimType>: ToArg<PrimExpr<T>> + Sized {
     fn to_func_arg(self, shared: &StackShared) -> PrimExpr<T> {
-        return PrimExpr(shared.clone(), self.to_expr_raw(), Default::default());
+        PrimExpr(shared.clone(), self.to_arg(), Default::default())
     }
 }
 
+impl<T: PrimType> ToFuncArg<T> for T { }
+
 impl<T: PrimType> ToFuncArg<T> for PrimExpr<T> {
     fn to_func_arg(self, _shared: &StackShared) -> PrimExpr<T> {
-        return self;
+        self
+    }
+}
+
+impl<T: PrimType> ToFuncArg<T> for &PrimExpr<T> {
+    fn to_func_arg(self, _shared: &StackShared) -> PrimExpr<T> {
+        self.clone()
     }
 }
 
 pub struct Func {
     pub(crate) shared: StackShared,
-    pub(crate) data: String,
-    pub(crate) first: bool,
+    pub(crate) name: String,
+    pub(crate) args: Vec<Expression>,
 }
 
 impl Func {
     /// Add an argument to the function call
     pub fn a<T: PrimType>(mut self, s: impl ToFuncArg<T>) -> Self {
-        if !self.first {
-            self.data.push_str(", ");
-        } else {
-            self.first = false;
-        }
-        let (_, s) = s.to_func_arg(&self.shared).expr_raw();
-        self.data.push_str(&s);
+        self.args.push(s.to_func_arg(&self.shared).1);
         self
     }
 
     /// Return an expression representing indexing the result of the function call
     pub fn index<T: PrimType>(&self, i: usize) -> PrimExpr<T> {
-        PrimExpr(self.shared.clone(), format!("{})[{}]", self.data, i), std::marker::PhantomData::default())
+        PrimExpr(
+            self.shared.clone(),
+            <Func as Expr<T>>::expr(self).1.index(Expression::Literal(i.into())),
+            std::marker::PhantomData,
+        )
     }
 }
 
 impl<T: PrimType> Expr<T> for Func {
-    fn expr_raw(&self) -> (&crate::StackShared, String) {
-        (&self.shared, format!("{})", self.data))
+    fn expr(&self) -> (&crate::StackShared, Expression) {
+        (&self.shared, Expression::Call(self.name.clone(), self.args.clone()))
     }
 }
 
 impl<T: PrimType> Into<PrimField<T>> for Func {
     fn into(self) -> PrimField<T> {
-        PrimField::Sentinel(<dyn Expr<T>>::expr_sentinel(&self))
+        PrimField::Expr(<Func as Expr<T>>::expr(&self).1)
     }
 }
 
 impl<T: PrimType> Into<PrimExpr<T>> for Func {
     fn into(self) -> PrimExpr<T> {
-        let (s, raw) = <Func as Expr<T>>::expr_raw(&self);
-        return PrimExpr(s.clone(), raw, std::marker::PhantomData::default());
+        let (s, e) = <Func as Expr<T>>::expr(&self);
+        PrimExpr(s.clone(), e, std::marker::PhantomData)
+    }
+}
+
+/// Marker for Terraform `number` parameters in `ToArg`, accepting either `i64` or
+/// `f64` values and expressions.
+pub struct Number;
+
+/// Marker for Terraform `dynamic` parameters in `ToArg`, accepting any value.
+pub struct Dynamic;
+
+/// Values that can be passed to a parameter of Terraform type `K` in the generated
+/// function wrappers, ex: `PrimExpr<String>` for `string`, `ListRef<K>` for
+/// `list(...)`, `Number` and `Dynamic`.
+pub trait ToArg<K> {
+    fn to_arg(self) -> Expression;
+}
+
+impl<T: PrimType> ToArg<PrimExpr<T>> for T {
+    fn to_arg(self) -> Expression {
+        self.to_expr()
+    }
+}
+
+impl ToArg<PrimExpr<String>> for &str {
+    fn to_arg(self) -> Expression {
+        self.to_string().to_expr()
+    }
+}
+
+impl<T: PrimType> ToArg<PrimExpr<T>> for PrimExpr<T> {
+    fn to_arg(self) -> Expression {
+        self.1
+    }
+}
+
+impl<T: PrimType> ToArg<PrimExpr<T>> for &PrimExpr<T> {
+    fn to_arg(self) -> Expression {
+        self.1.clone()
+    }
+}
+
+impl<T: PrimType> ToArg<PrimExpr<T>> for PrimField<T> {
+    fn to_arg(self) -> Expression {
+        match self {
+            PrimField::Literal(v) => v.to_expr(),
+            PrimField::Expr(e) => e,
+        }
+    }
+}
+
+impl<T> ToArg<ListRef<T>> for ListRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base
+    }
+}
+
+impl<T> ToArg<ListRef<T>> for &ListRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<T, X: ToArg<T>> ToArg<ListRef<T>> for Vec<X> {
+    fn to_arg(self) -> Expression {
+        Expression::List(self.into_iter().map(|v| v.to_arg()).collect())
+    }
+}
+
+// Sets can be iterated like lists
+impl<T: Ref> ToArg<ListRef<T>> for &SetRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<T: Ref> ToArg<SetRef<T>> for SetRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base
+    }
+}
+
+impl<T: Ref> ToArg<SetRef<T>> for &SetRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+// Terraform converts lists to sets automatically
+impl<T: Ref> ToArg<SetRef<T>> for &ListRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<T: Ref, X: ToArg<T>> ToArg<SetRef<T>> for Vec<X> {
+    fn to_arg(self) -> Expression {
+        Expression::List(self.into_iter().map(|v| v.to_arg()).collect())
+    }
+}
+
+impl<T: Ref> ToArg<RecRef<T>> for RecRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base
+    }
+}
+
+impl<T: Ref> ToArg<RecRef<T>> for &RecRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<T: Ref, X: ToArg<T>> ToArg<RecRef<T>> for BTreeMap<String, X> {
+    fn to_arg(self) -> Expression {
+        Expression::Object(self.into_iter().map(|(k, v)| (k, v.to_arg())).collect())
+    }
+}
+
+macro_rules! number_arg{
+    ($t: ty) => {
+        impl ToArg<Number> for $t {
+            fn to_arg(self) -> Expression {
+                self.to_expr()
+            }
+        }
+        impl ToArg<Number> for PrimExpr<$t> {
+            fn to_arg(self) -> Expression {
+                self.1
+            }
+        }
+        impl ToArg<Number> for &PrimExpr<$t> {
+            fn to_arg(self) -> Expression {
+                self.1.clone()
+            }
+        }
+    };
+}
+
+number_arg!(f64);
+
+// Covers `i64` values and expressions, and keeps code generic over `ToFuncArg<i64>`
+// working with the generated wrappers.
+impl<X: ToFuncArg<i64>> ToArg<Number> for X {
+    fn to_arg(self) -> Expression {
+        ToArg::<PrimExpr<i64>>::to_arg(self)
+    }
+}
+
+macro_rules! dynamic_prim_arg{
+    ($t: ty) => {
+        impl ToArg<Dynamic> for $t {
+            fn to_arg(self) -> Expression {
+                self.to_expr()
+            }
+        }
+    };
+}
+
+dynamic_prim_arg!(String);
+dynamic_prim_arg!(bool);
+dynamic_prim_arg!(i64);
+dynamic_prim_arg!(f64);
+
+impl ToArg<Dynamic> for &str {
+    fn to_arg(self) -> Expression {
+        self.to_string().to_expr()
     }
 }
+
+impl<T: PrimType> ToArg<Dynamic> for PrimExpr<T> {
+    fn to_arg(self) -> Expression {
+        self.1
+    }
+}
+
+impl<T: PrimType> ToArg<Dynamic> for &PrimExpr<T> {
+    fn to_arg(self) -> Expression {
+        self.1.clone()
+    }
+}
+
+impl<T> ToArg<Dynamic> for &ListRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<T: Ref> ToArg<Dynamic> for &SetRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<T: Ref> ToArg<Dynamic> for &RecRef<T> {
+    fn to_arg(self) -> Expression {
+        self.base.clone()
+    }
+}
+
+impl<X: ToArg<Dynamic>> ToArg<Dynamic> for Vec<X> {
+    fn to_arg(self) -> Expression {
+        Expression::List(self.into_iter().map(|v| v.to_arg()).collect())
+    }
+}
+
+impl<X: ToArg<Dynamic>> ToArg<Dynamic> for BTreeMap<String, X> {
+    fn to_arg(self) -> Expression {
+        Expression::Object(self.into_iter().map(|(k, v)| (k, v.to_arg())).collect())
+    }
+}
+
+/// Values for a variadic parameter of Terraform type `K`: a `Vec` or a tuple of
+/// values, each of which can be passed as `K`.
+pub trait ToArgs<K> {
+    fn to_args(self) -> Vec<Expression>;
+}
+
+impl<K, X: ToArg<K>> ToArgs<K> for Vec<X> {
+    fn to_args(self) -> Vec<Expression> {
+        self.into_iter().map(|v| v.to_arg()).collect()
+    }
+}
+
+macro_rules! tuple_args{
+    ($($x: ident), *) => {
+        impl < K, $($x: ToArg < K >), * > ToArgs < K > for ($($x,) *) {
+            #[allow(non_snake_case)]
+            fn to_args(self) -> Vec<Expression> {
+                let ($($x,) *) = self;
+                vec![$($x.to_arg()), *]
+            }
+        }
+    };
+}
+
+tuple_args!();
+tuple_args!(A);
+tuple_args!(A, B);
+tuple_args!(A, B, C);
+tuple_args!(A, B, C, D);
+tuple_args!(A, B, C, D, E);
+tuple_args!(A, B, C, D, E, F);
+tuple_args!(A, B, C, D, E, F, G);
+tuple_args!(A, B, C, D, E, F, G, H);
+
+/// Call function `name` with converted arguments, producing a typed result. This is
+/// used by the generated function wrappers.
+pub fn call_func<R: Ref>(stack: &Stack, name: &str, args: Vec<Expression>) -> R {
+    R::new(stack.shared.clone(), Expression::Call(name.to_string(), args))
+}
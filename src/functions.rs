@@ -0,0 +1,19 @@
+//! Typed wrappers for Terraform's built-in functions, generated at build time from
+//! `src/functions.json`. To refresh the signatures for a new Terraform version run
+//! `terraform metadata functions -json > src/functions.json`.
+use crate::{
+    call_func,
+    Dynamic,
+    ListRef,
+    Number,
+    PrimExpr,
+    PrimNumber,
+    RecRef,
+    Ref,
+    SetRef,
+    Stack,
+    ToArg,
+    ToArgs,
+};
+
+include!(concat!(env!("OUT_DIR"), "/functions.rs"));
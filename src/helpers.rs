@@ -1,43 +1,43 @@
 use crate::{
+    expr::Expression,
+    functions::{
+        tf_trimprefix,
+        tf_trimsuffix,
+    },
     Stack,
     ToFuncArg,
     PrimExpr,
+    PrimType,
 };
 
-/// Generates a call to Terraform method `base64encode`.
-pub fn tf_base64encode(stack: &Stack, e: impl ToFuncArg<String>) -> PrimExpr<String> {
-    return stack.func("base64encode").a(e).into();
-}
-
-/// Generates a call to Terraform method `base64decode`.
-pub fn tf_base64decode(stack: &Stack, e: impl ToFuncArg<String>) -> PrimExpr<String> {
-    return stack.func("base64decode").a(e).into();
-}
-
-/// Generates a call to Terraform method `substr`.
-pub fn tf_substr(
-    stack: &Stack,
-    e: impl ToFuncArg<String>,
-    offset: impl ToFuncArg<i64>,
-    length: impl ToFuncArg<i64>,
-) -> PrimExpr<String> {
-    return stack.func("substr").a(e).a(offset).a(length).into();
+/// Generates a conditional expression, `cond ? a : b`.
+pub fn tf_if<T: PrimType>(
+    cond: impl Into<PrimExpr<bool>>,
+    a: impl ToFuncArg<T>,
+    b: impl ToFuncArg<T>,
+) -> PrimExpr<T> {
+    let cond = cond.into();
+    let a = a.to_func_arg(&cond.0);
+    let b = b.to_func_arg(&cond.0);
+    PrimExpr(cond.0, Expression::Conditional(Box::new(cond.1), Box::new(a.1), Box::new(b.1)), Default::default())
 }
 
 /// Generates a call to Terraform method `trimsuffix`.
+#[deprecated(note = "use the generated `tf_trimsuffix`")]
 pub fn tf_trim_suffix(
     stack: &Stack,
     original: impl ToFuncArg<String>,
     suffix: impl ToFuncArg<String>,
 ) -> PrimExpr<String> {
-    return stack.func("trimsuffix").a(original).a(suffix).into();
+    tf_trimsuffix(stack, original, suffix)
 }
 
 /// Generates a call to Terraform method `trimprefix`.
+#[deprecated(note = "use the generated `tf_trimprefix`")]
 pub fn tf_trim_prefix(
     stack: &Stack,
     original: impl ToFuncArg<String>,
     prefix: impl ToFuncArg<String>,
 ) -> PrimExpr<String> {
-    return stack.func("trimprefix").a(original).a(prefix).into();
+    tf_trimprefix(stack, original, prefix)
 }
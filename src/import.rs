@@ -0,0 +1,8 @@
+use serde::Serialize;
+use crate::PrimField;
+
+#[derive(Serialize)]
+pub(crate) struct Import {
+    pub(crate) to: String,
+    pub(crate) id: PrimField<String>,
+}
@@ -7,6 +7,7 @@ use std::{
     collections::{
         BTreeMap,
         HashMap,
+        HashSet,
     },
     fs::{
         self,
@@ -26,7 +27,6 @@ use std::{
         Stdio,
     },
     rc::Rc,
-    str::FromStr,
     marker::PhantomData,
 };
 use serde::{
@@ -40,50 +40,108 @@ use serde_json::{
 };
 use thiserror::Error;
 
-pub(crate) mod utils;
+pub mod backend;
 pub mod ref_;
 pub mod expr;
 pub mod func;
+pub mod ops;
+pub mod template;
+pub mod local;
+pub mod module;
+pub mod moved;
+pub(crate) mod import;
+pub mod removed;
+pub mod check;
+pub mod provisioner;
 pub mod list_field;
 pub mod list_ref;
 pub mod rec_field;
 pub mod rec_ref;
 pub mod output;
 pub mod prim_field;
+pub mod dynamic_field;
 pub mod prim_ref;
 pub mod set_field;
 pub mod set_ref;
 pub mod variable;
+pub mod value;
 pub mod helpers;
+pub mod functions;
 
+pub use backend::*;
 pub use ref_::*;
 pub use expr::*;
 pub use func::*;
+pub use template::*;
+pub use local::*;
+pub use module::*;
+pub use moved::*;
+use import::Import;
+pub use removed::*;
+pub use check::*;
+pub use provisioner::*;
 pub use list_field::*;
 pub use list_ref::*;
 pub use rec_field::*;
 pub use rec_ref::*;
 pub use output::*;
 pub use prim_field::*;
+pub use dynamic_field::*;
 pub use prim_ref::*;
 pub use set_field::*;
 pub use set_ref::*;
-use utils::REPLACE_EXPRS;
 pub use variable::*;
+pub use value::*;
+pub use terrars_derive::{
+    TfOutputs,
+    TfVariables,
+};
 pub use helpers::*;
-
-/// Use this to create a new stack.
-pub struct BuildStack {}
+pub use functions::*;
+
+/// Use this to create a new stack. All fields are optional, ex: `BuildStack {
+/// backend: ..., ..Default::default() }`.
+#[derive(Default)]
+pub struct BuildStack {
+    /// Where Terraform stores state. Defaults to local, in `state.tfstate` in the
+    /// stack directory.
+    pub backend: Backend,
+    /// Use HCP Terraform (Terraform Cloud) for state and operations. This replaces
+    /// the backend.
+    pub cloud: Option<Cloud>,
+    /// Require a Terraform version matching this constraint, ex: `~> 1.9`.
+    pub required_version: Option<String>,
+    /// Experimental language features to enable.
+    pub experiments: Vec<String>,
+    /// Module metadata to pass to providers, keyed by the provider's short name (ex:
+    /// `aws`). Values must be literal, references aren't allowed here.
+    pub provider_meta: BTreeMap<String, Value>,
+}
 
 impl BuildStack {
     pub fn build(self) -> Stack {
         return Stack {
+            backend: self.backend,
+            cloud: self.cloud,
+            required_version: self.required_version,
+            experiments: self.experiments,
+            provider_meta: self.provider_meta,
+            provider_requirements: Default::default(),
             providers: Default::default(),
             variables: Default::default(),
+            locals: Default::default(),
+            modules: Default::default(),
             datasources: Default::default(),
             resources: Default::default(),
             outputs: Default::default(),
-            shared: StackShared(Rc::new(RefCell::new(StackShared_ { replace_exprs: Default::default() }))),
+            moved: Default::default(),
+            removed: Default::default(),
+            checks: Default::default(),
+            shared: StackShared(Rc::new(RefCell::new(StackShared_ {
+                imports: Default::default(),
+                provider_types: Default::default(),
+                loop_vars: 0,
+            }))),
         };
     }
 }
@@ -93,15 +151,29 @@ pub enum ComponentType {
     ProviderType,
     Provider,
     Variable,
+    Local,
+    Module,
+    Import,
     Datasource,
     Resource,
     Output,
+    Check,
 }
 
 #[derive(Error, Debug)]
 pub enum StackError {
     #[error("Duplicate {0:?} with tf_id {1}")]
     Duplicate(ComponentType, String),
+    #[error("Providers of type {0} have conflicting {1}: {2} and {3}")]
+    ProviderConflict(String, String, String, String),
+    #[error("Variable {0} default isn't a literal value: {1}")]
+    VariableDefault(String, String),
+    #[error("Resource {0} has an invalid lifecycle attribute: {1}")]
+    LifecycleAttr(String, String),
+    #[error("Datasource {0} is scoped to both check {1} and check {2}")]
+    DatasourceScopedTwice(String, String, String),
+    #[error("Datasource {0} scoped to check {1} isn't in the stack")]
+    ScopedDatasourceMissing(String, String),
 }
 
 #[derive(Error, Debug)]
@@ -124,72 +196,168 @@ pub enum RunError {
         serde_json::Error,
     ),
     #[error("Command {0:?} failed with result {1:?}")]
-    CommandError(Command, process::ExitStatus),
+    CommandError(Box<Command>, process::ExitStatus),
 }
 
 struct StackShared_ {
-    replace_exprs: Vec<(String, String)>,
+    imports: Vec<Import>,
+    provider_types: Vec<(String, Value)>,
+    // Count of loop variables named so far, to keep names unique within the stack
+    loop_vars: usize,
 }
 
 #[derive(Clone)]
 pub struct StackShared(Rc<RefCell<StackShared_>>);
 
 impl StackShared {
-    pub fn add_sentinel(&self, v: &str) -> String {
-        let mut m = self.0.borrow_mut();
-        let k = format!("_TERRARS_SENTINEL_{}_", m.replace_exprs.len());
-        m.replace_exprs.push((k.clone(), format!("${{{}}}", v)));
-        k
+    /// Import existing infrastructure identified by `id` into the resource with
+    /// address `to`.
+    pub fn add_import(&self, to: String, id: PrimField<String>) {
+        self.0.borrow_mut().imports.push(Import {
+            to,
+            id,
+        });
+    }
+
+    /// Declare provider `provider` (the provider's short name, ex: `aws`) as required
+    /// with type `provider_type` (`source` and `version`) without configuring it.
+    /// Used by provider-defined functions. Conflicts with other requirements for the
+    /// same provider are errors when serializing the stack.
+    pub fn add_provider_type(&self, provider: impl ToString, provider_type: Value) {
+        let provider_type = (provider.to_string(), provider_type);
+        let mut shared = self.0.borrow_mut();
+        if !shared.provider_types.contains(&provider_type) {
+            shared.provider_types.push(provider_type);
+        }
+    }
+
+    /// Get a number for naming a new loop variable, unique within the stack.
+    pub(crate) fn next_loop_var(&self) -> usize {
+        let mut shared = self.0.borrow_mut();
+        let out = shared.loop_vars;
+        shared.loop_vars += 1;
+        out
     }
 }
 
 pub struct Stack {
+    backend: Backend,
+    cloud: Option<Cloud>,
+    required_version: Option<String>,
+    experiments: Vec<String>,
+    provider_meta: BTreeMap<String, Value>,
+    provider_requirements: BTreeMap<String, Value>,
     providers: Vec<Rc<dyn Provider>>,
     variables: Vec<Rc<dyn VariableTrait>>,
+    locals: Vec<Rc<dyn LocalTrait>>,
+    modules: Vec<Rc<dyn Module_>>,
     datasources: Vec<Rc<dyn Datasource_>>,
     resources: Vec<Rc<dyn Resource_>>,
     outputs: Vec<Rc<dyn Output>>,
+    moved: Vec<Moved>,
+    removed: Vec<Rc<Removed_>>,
+    checks: Vec<Rc<Check_>>,
     pub shared: StackShared,
 }
 
 impl Stack {
     /// Turn a value into into an expression that evaluates to that value (ex:
     /// `expr_lit(44)` or `expr_lit("hi")`) for use in other expressions, like
-    /// Terraform function calls. NOTE: Converting from an expression to a string then
-    /// back to an expression again will result in double escaping (broken SENTINEL
-    /// junk).
+    /// Terraform function calls.
     pub fn expr_lit<T: PrimType>(&self, expr: T) -> PrimExpr<T> {
-        PrimExpr(self.shared.clone(), expr.to_expr_raw(), Default::default())
+        PrimExpr(self.shared.clone(), expr.to_expr(), Default::default())
     }
 
     /// Turn a raw expression string into a `PrimExpr` - the string must be properly
-    /// escaped, etc.
+    /// escaped, etc. Use `.raw()` to include other expressions.
     pub fn expr<T: PrimType>(&self, expr: impl ToString) -> PrimExpr<T> {
-        PrimExpr(self.shared.clone(), expr.to_string(), Default::default())
+        PrimExpr(self.shared.clone(), Expression::Raw(expr.to_string()), Default::default())
+    }
+
+    /// Get `count.index`, the index of the current instance in a resource,
+    /// datasource, or module with `set_count`. Only valid within the definition of
+    /// that resource, datasource, or module.
+    pub fn count_index(&self) -> PrimExpr<i64> {
+        PrimExpr(self.shared.clone(), Expression::Ref("count.index".into()), Default::default())
     }
 
     /// Start a new function call expression
     pub fn func(&self, name: &str) -> Func {
         Func {
             shared: self.shared.clone(),
-            data: format!("{}(", name),
-            first: true,
+            name: name.to_string(),
+            args: vec![],
+        }
+    }
+
+    /// Start a new string template expression
+    pub fn template(&self) -> TemplateBuilder {
+        TemplateBuilder {
+            shared: self.shared.clone(),
+            parts: vec![],
+            strip: Default::default(),
         }
     }
 
+    /// Require provider `provider` (the provider's short name, ex: `aws`) from
+    /// `source` (ex: `hashicorp/aws`) with versions matching the constraint
+    /// `constraint` (ex: `~> 5.0`), replacing the version of any generated providers
+    /// of that type. Providers only used by modules can be required this way too. A
+    /// different source than a generated provider's is an error when serializing the
+    /// stack.
+    pub fn require_provider(&mut self, provider: impl ToString, source: impl ToString, constraint: impl ToString) {
+        self.provider_requirements.insert(provider.to_string(), json!({
+            "source": source.to_string(),
+            "version": constraint.to_string()
+        }));
+    }
+
     /// Convert the stack to json bytes.
-    pub fn serialize(&self, state_path: &Path) -> Result<Vec<u8>, StackError> {
-        REPLACE_EXPRS.with(move |f| {
-            *f.borrow_mut() = Some(self.shared.0.borrow().replace_exprs.clone());
-        });
+    pub fn serialize(&self) -> Result<Vec<u8>, StackError> {
         let mut required_providers = BTreeMap::new();
-        for p in &self.providers {
-            match required_providers.entry(p.extract_type_tf_id()) {
+        let mut add_required_provider = |tf_id: String, mut new_type: Value| -> Result<(), StackError> {
+            if let Some(requirement) = self.provider_requirements.get(&tf_id) {
+                if new_type["source"] != requirement["source"] {
+                    Err(
+                        StackError::ProviderConflict(
+                            tf_id.clone(),
+                            "source".into(),
+                            requirement["source"].to_string(),
+                            new_type["source"].to_string(),
+                        ),
+                    )?;
+                }
+                new_type["version"] = requirement["version"].clone();
+            }
+            match required_providers.entry(tf_id.clone()) {
                 std::collections::btree_map::Entry::Vacant(v) => {
-                    v.insert(p.extract_provider_type());
+                    v.insert(new_type);
+                },
+                std::collections::btree_map::Entry::Occupied(o) => {
+                    for k in ["source", "version"] {
+                        if o.get()[k] != new_type[k] {
+                            Err(
+                                StackError::ProviderConflict(
+                                    tf_id.clone(),
+                                    k.to_string(),
+                                    o.get()[k].to_string(),
+                                    new_type[k].to_string(),
+                                ),
+                            )?;
+                        }
+                    }
                 },
-                std::collections::btree_map::Entry::Occupied(_) => { },
             };
+            Ok(())
+        };
+        for p in &self.providers {
+            add_required_provider(p.extract_type_tf_id(), p.extract_provider_type())?;
+        }
+        for (k, provider_type) in &self.shared.0.borrow().provider_types {
+            add_required_provider(k.clone(), provider_type.clone())?;
+        }
+        for (k, requirement) in &self.provider_requirements {
+            required_providers.entry(k.clone()).or_insert_with(|| requirement.clone());
         }
         let mut providers = BTreeMap::new();
         for p in &self.providers {
@@ -197,12 +365,50 @@ impl Stack {
         }
         let mut variables = BTreeMap::new();
         for v in &self.variables {
-            if variables.insert(v.extract_tf_id(), v.extract_value()).is_some() {
+            if variables.insert(v.extract_tf_id(), v.extract_value()?).is_some() {
                 Err(StackError::Duplicate(ComponentType::Variable, v.extract_tf_id()))?;
             }
         }
+        let mut locals = BTreeMap::new();
+        for l in &self.locals {
+            if locals.insert(l.extract_tf_id(), l.extract_value()).is_some() {
+                Err(StackError::Duplicate(ComponentType::Local, l.extract_tf_id()))?;
+            }
+        }
+        let mut modules = BTreeMap::new();
+        for m in &self.modules {
+            if modules.insert(m.extract_tf_id(), m.extract_value()).is_some() {
+                Err(StackError::Duplicate(ComponentType::Module, m.extract_tf_id()))?;
+            }
+        }
+        let mut scoped_data = HashMap::new();
+        for c in &self.checks {
+            if let Some(d) = c.extract_datasource() {
+                match scoped_data.entry(d) {
+                    std::collections::hash_map::Entry::Vacant(v) => {
+                        v.insert((c.extract_tf_id(), None));
+                    },
+                    std::collections::hash_map::Entry::Occupied(o) => {
+                        Err(StackError::DatasourceScopedTwice(o.key().clone(), o.get().0.clone(), c.extract_tf_id()))?;
+                    },
+                }
+            }
+        }
         let mut data = BTreeMap::new();
         for d in &self.datasources {
+            if let Some((_, scoped)) =
+                scoped_data.get_mut(&format!("data.{}.{}", d.extract_datasource_type(), d.extract_tf_id())) {
+                if scoped.is_some() {
+                    Err(StackError::Duplicate(ComponentType::Datasource, d.extract_tf_id()))?;
+                }
+                *scoped =
+                    Some(
+                        json!(
+                            BTreeMap::from([(d.extract_datasource_type(), BTreeMap::from([(d.extract_tf_id(), d.extract_value())]))])
+                        ),
+                    );
+                continue;
+            }
             if data
                 .entry(d.extract_datasource_type())
                 .or_insert_with(BTreeMap::new)
@@ -216,7 +422,7 @@ impl Stack {
             if resources
                 .entry(r.extract_resource_type())
                 .or_insert_with(BTreeMap::new)
-                .insert(r.extract_tf_id(), r.extract_value())
+                .insert(r.extract_tf_id(), r.extract_value()?)
                 .is_some() {
                 Err(StackError::Duplicate(ComponentType::Resource, r.extract_tf_id()))?;
             }
@@ -227,21 +433,52 @@ impl Stack {
                 Err(StackError::Duplicate(ComponentType::Output, o.extract_tf_id()))?;
             }
         }
-        let mut out = BTreeMap::new();
-        out.insert("terraform", json!({
-            "backend": {
-                "local": {
-                    "path": state_path.to_string_lossy(),
+        let mut checks = BTreeMap::new();
+        for c in &self.checks {
+            let data = match c.extract_datasource() {
+                Some(d) => match scoped_data.get_mut(&d).and_then(|(_, v)| v.take()) {
+                    Some(v) => Some(v),
+                    None => Err(StackError::ScopedDatasourceMissing(d, c.extract_tf_id()))?,
                 },
+                None => None,
+            };
+            if checks.insert(c.extract_tf_id(), c.extract_value(data)).is_some() {
+                Err(StackError::Duplicate(ComponentType::Check, c.extract_tf_id()))?;
+            }
+        }
+        let mut out = BTreeMap::new();
+        let mut terraform = serde_json::Map::new();
+        if let Some(v) = &self.required_version {
+            terraform.insert("required_version".into(), json!(v));
+        }
+        if !self.experiments.is_empty() {
+            terraform.insert("experiments".into(), json!(self.experiments));
+        }
+        match &self.cloud {
+            Some(c) => {
+                terraform.insert("cloud".into(), json!(c));
             },
-            "required_providers": required_providers,
-        }));
+            None => {
+                terraform.insert("backend".into(), json!(self.backend));
+            },
+        }
+        terraform.insert("required_providers".into(), json!(required_providers));
+        if !self.provider_meta.is_empty() {
+            terraform.insert("provider_meta".into(), json!(self.provider_meta));
+        }
+        out.insert("terraform", Value::Object(terraform));
         if !providers.is_empty() {
             out.insert("provider", json!(providers));
         }
         if !variables.is_empty() {
             out.insert("variable", json!(variables));
         }
+        if !locals.is_empty() {
+            out.insert("locals", json!(locals));
+        }
+        if !modules.is_empty() {
+            out.insert("module", json!(modules));
+        }
         if !data.is_empty() {
             out.insert("data", json!(data));
         }
@@ -251,7 +488,27 @@ impl Stack {
         if !outputs.is_empty() {
             out.insert("output", json!(outputs));
         }
-        REPLACE_EXPRS.with(|f| *f.borrow_mut() = None);
+        if !checks.is_empty() {
+            out.insert("check", json!(checks));
+        }
+        if !self.moved.is_empty() {
+            out.insert("moved", json!(self.moved));
+        }
+        if !self.removed.is_empty() {
+            out.insert("removed", Value::Array(self.removed.iter().map(|r| r.extract_value()).collect()));
+        }
+        {
+            let shared = self.shared.0.borrow();
+            let mut seen_imports = HashSet::new();
+            for i in &shared.imports {
+                if !seen_imports.insert(&i.to) {
+                    Err(StackError::Duplicate(ComponentType::Import, i.to.clone()))?;
+                }
+            }
+            if !shared.imports.is_empty() {
+                out.insert("import", json!(shared.imports));
+            }
+        }
         let res = serde_json::to_vec_pretty(&out).unwrap();
         Ok(res)
     }
@@ -268,21 +525,75 @@ impl Stack {
         self.resources.push(v);
     }
 
+    pub fn add_module(&mut self, v: Rc<dyn Module_>) {
+        self.modules.push(v);
+    }
+
+    /// Import existing infrastructure identified by `id` into resource `to`. Generated
+    /// resources also have an `import_from` method which does the same thing.
+    pub fn add_import(&mut self, to: &impl Referable, id: impl Into<PrimField<String>>) {
+        self.shared.add_import(to.extract_ref(), id.into());
+    }
+
+    /// Stop managing the resource of type `R` with id `tf_id`. If `destroy` is false
+    /// the infrastructure is left in place, otherwise it's destroyed.
+    pub fn add_removed<R: ResourceType>(&mut self, tf_id: impl ToString, destroy: bool) -> Removed {
+        self.add_removed_addr(format!("{}.{}", R::resource_type(), tf_id.to_string()), destroy)
+    }
+
+    /// Like `add_removed` but with a raw address, ex: `module.my_module`.
+    pub fn add_removed_addr(&mut self, from: impl ToString, destroy: bool) -> Removed {
+        let out = Rc::new(Removed_::new(from.to_string(), destroy));
+        self.removed.push(out.clone());
+        Removed(out)
+    }
+
+    /// Tell Terraform the resource or module `from` was renamed to `to`, so the
+    /// existing infrastructure is kept instead of being destroyed and recreated.
+    pub fn add_moved(&mut self, from: &impl Referable, to: &impl Referable) {
+        self.add_moved_addr(from.extract_ref(), to.extract_ref());
+    }
+
+    /// Like `add_moved` but with raw addresses, ex: `aws_instance.old_name`. Use
+    /// this when the old resource is no longer defined in the stack.
+    pub fn add_moved_addr(&mut self, from: impl ToString, to: impl ToString) {
+        self.moved.push(Moved {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
     /// Serialize the stack to a file and run a Terraform command on it. If variables
-    /// are provided, they must be a single-level struct where all values are
-    /// primitives (i64, f64, String, bool).
-    pub fn run<V: Serialize>(&self, path: &Path, variables: Option<&V>, mode: &str) -> Result<(), RunError> {
+    /// are provided they're written to a var-file - use the `#[derive(TfVariables)]`
+    /// struct whose `declare` created the variables, or `None::<&()>` for none.
+    pub fn run<V: TfVariables>(&self, path: &Path, variables: Option<&V>, mode: &str) -> Result<(), RunError> {
         create_dir_all(path).map_err(|e| RunError::FsError(path.to_path_buf(), e))?;
-        let state_name = "state.tfstate";
-        fs::write(&path.join("stack.tf.json"), &self.serialize(&PathBuf::from_str(state_name).unwrap())?)?;
-        let state_path = path.join(state_name);
-        if !state_path.exists() {
+        let stack_path = path.join("stack.tf.json");
+        let stack = self.serialize()?;
+
+        // Init is needed the first time and whenever the backend, providers or module
+        // sources change, regardless of where the state is stored. The config of the
+        // last successful init is saved in `.terraform` so a failed init is retried.
+        let init_config = {
+            let stack = serde_json::from_slice::<Value>(&stack)?;
+            let mut modules = BTreeMap::new();
+            if let Some(Value::Object(m)) = stack.get("module") {
+                for (k, v) in m {
+                    modules.insert(k.clone(), (v.get("source").cloned(), v.get("version").cloned()));
+                }
+            }
+            serde_json::to_vec(&json!([stack.get("terraform"), modules]))?
+        };
+        let init_config_path = path.join(".terraform").join("terrars_init.json");
+        fs::write(&stack_path, &stack)?;
+        if fs::read(&init_config_path).ok().as_ref() != Some(&init_config) {
             let mut command = Command::new("terraform");
-            command.current_dir(&path).arg("init");
+            command.current_dir(path).args(["init", "-input=false"]);
             let res = command.status()?;
             if !res.success() {
-                return Err(RunError::CommandError(command, res));
+                return Err(RunError::CommandError(Box::new(command), res));
             }
+            fs::write(&init_config_path, &init_config)?;
         }
         let mut command = Command::new("terraform");
         command.current_dir(&path).arg(mode);
@@ -292,44 +603,44 @@ impl Stack {
             command.arg(format!("-var-file={}", vars_file.path().to_string_lossy()));
             let res = command.status()?;
             if !res.success() {
-                return Err(RunError::CommandError(command, res))?;
+                return Err(RunError::CommandError(Box::new(command), res))?;
             }
         } else {
             let res = command.status()?;
             if !res.success() {
-                return Err(RunError::CommandError(command, res))?;
+                return Err(RunError::CommandError(Box::new(command), res))?;
             }
         }
         Ok(())
     }
 
     /// Gets the current outputs from an applied stack. `path` is the directory in
-    /// which the .tf.json file was written. The output struct must be a single level
-    /// and only have primitive values (i64, f64, String, bool).
-    pub fn get_output<O: DeserializeOwned>(&self, path: &Path) -> Result<O, RunError> {
+    /// which the .tf.json file was written. `O` is the `#[derive(TfOutputs)]` struct
+    /// whose `Build*` struct declared the outputs.
+    pub fn get_output<O: TfOutputs>(&self, path: &Path) -> Result<O, RunError> {
         let mut command = Command::new("terraform");
         let res = command.current_dir(&path).stderr(Stdio::inherit()).args(&["output", "-json"]).output()?;
         if !res.status.success() {
-            return Err(RunError::CommandError(command, res.status));
+            return Err(RunError::CommandError(Box::new(command), res.status));
         }
 
-        // Redeserialize... hack
-        #[derive(Deserialize)]
-        struct Var {
-            value: Value,
-        }
+        Ok(parse_outputs(&res.stdout)?)
+    }
+}
 
-        Ok(
-            serde_json::from_slice(
-                &serde_json::to_vec(
-                    &serde_json::from_slice::<HashMap<String, Var>>(&res.stdout)?
-                        .into_iter()
-                        .map(|(k, v)| (k, v.value))
-                        .collect::<HashMap<String, Value>>(),
-                )?,
-            )?,
-        )
+/// Parse the output of `terraform output -json`.
+fn parse_outputs<O: DeserializeOwned>(raw: &[u8]) -> Result<O, serde_json::Error> {
+    // Strip the type and sensitivity info, leaving `{name: value}`
+    #[derive(Deserialize)]
+    struct Var {
+        value: Value,
     }
+
+    serde_json::from_value(
+        Value::Object(
+            serde_json::from_slice::<HashMap<String, Var>>(raw)?.into_iter().map(|(k, v)| (k, v.value)).collect(),
+        ),
+    )
 }
 
 // Generated traits
@@ -353,8 +664,17 @@ pub trait Datasource_ {
 
 pub trait Resource: Referable { }
 
+pub trait ResourceType {
+    fn resource_type() -> &'static str;
+}
+
 pub trait Resource_ {
     fn extract_resource_type(&self) -> String;
+    fn extract_tf_id(&self) -> String;
+    fn extract_value(&self) -> Result<Value, StackError>;
+}
+
+pub trait Module_ {
     fn extract_tf_id(&self) -> String;
     fn extract_value(&self) -> Value;
 }
@@ -373,6 +693,14 @@ pub enum IgnoreChanges {
     Refs(Vec<String>),
 }
 
+/// A `precondition` or `postcondition`: fail the plan or apply with
+/// `error_message` if `condition` is false.
+#[derive(Serialize, PartialEq)]
+pub struct Condition {
+    pub condition: PrimField<bool>,
+    pub error_message: PrimField<String>,
+}
+
 #[derive(Serialize, Default, PartialEq)]
 pub struct ResourceLifecycle {
     pub create_before_destroy: bool,
@@ -380,11 +708,27 @@ pub struct ResourceLifecycle {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ignore_changes: Option<IgnoreChanges>,
     pub replace_triggered_by: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub precondition: Vec<Condition>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub postcondition: Vec<Condition>,
+    /// The first invalid `ignore_changes` or `replace_triggered_by` attribute, reported
+    /// when serializing the stack.
+    #[serde(skip)]
+    pub attr_error: Option<String>,
+}
+
+#[derive(Serialize, Default, PartialEq)]
+pub struct DatasourceLifecycle {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub precondition: Vec<Condition>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub postcondition: Vec<Condition>,
 }
 
 #[derive(Serialize)]
 pub struct DynamicBlock<T: Serialize> {
-    pub for_each: String,
+    pub for_each: Expression,
     pub iterator: String,
     pub content: T,
 }
@@ -435,10 +779,119 @@ impl<T: Ref> MapKV<T> {
     }
 
     pub fn key(&self) -> PrimExpr<String> {
-        PrimExpr::new(self.shared.clone(), "each.key".into())
+        PrimExpr::new(self.shared.clone(), Expression::Ref("each.key".into()))
     }
 
     pub fn value(&self) -> T {
-        T::new(self.shared.clone(), "each.value".into())
+        T::new(self.shared.clone(), Expression::Ref("each.value".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value,
+    };
+    use crate::{
+        parse_outputs,
+        BuildStack,
+        Secret,
+        StackError,
+    };
+
+    fn required_providers(stack: &crate::Stack) -> Result<Value, StackError> {
+        let out: Value = serde_json::from_slice(&stack.serialize()?).unwrap();
+        Ok(out["terraform"]["required_providers"].clone())
+    }
+
+    #[test]
+    fn require_provider() {
+        let mut stack = BuildStack::default().build();
+        stack.require_provider("fly", "andrewbaxter/fly", "~> 0.1");
+        assert_eq!(required_providers(&stack).unwrap(), json!({
+            "fly": {
+                "source": "andrewbaxter/fly",
+                "version": "~> 0.1"
+            }
+        }));
+    }
+
+    #[test]
+    fn require_provider_overrides_version() {
+        let mut stack = BuildStack::default().build();
+        stack.shared.add_provider_type("aws", json!({
+            "source": "hashicorp/aws",
+            "version": "5.0.0"
+        }));
+        stack.require_provider("aws", "hashicorp/aws", "~> 5.0");
+        assert_eq!(required_providers(&stack).unwrap(), json!({
+            "aws": {
+                "source": "hashicorp/aws",
+                "version": "~> 5.0"
+            }
+        }));
+    }
+
+    #[test]
+    fn require_provider_source_conflict() {
+        let mut stack = BuildStack::default().build();
+        stack.shared.add_provider_type("aws", json!({
+            "source": "hashicorp/aws",
+            "version": "5.0.0"
+        }));
+        stack.require_provider("aws", "someone/aws", "~> 5.0");
+        assert!(matches!(
+            required_providers(&stack),
+            Err(StackError::ProviderConflict(p, k, _, _)) if p == "aws" && k == "source"
+        ));
+    }
+
+    #[test]
+    fn provider_version_conflict() {
+        let stack = BuildStack::default().build();
+        stack.shared.add_provider_type("aws", json!({
+            "source": "hashicorp/aws",
+            "version": "5.0.0"
+        }));
+        stack.shared.add_provider_type("aws", json!({
+            "source": "hashicorp/aws",
+            "version": "5.1.0"
+        }));
+        assert!(matches!(
+            required_providers(&stack),
+            Err(StackError::ProviderConflict(p, k, _, _)) if p == "aws" && k == "version"
+        ));
+    }
+
+    #[test]
+    fn parse_nested_outputs() {
+        #[derive(serde::Deserialize)]
+        struct Outputs {
+            ips: Vec<Vec<String>>,
+            tags: std::collections::BTreeMap<String, Vec<String>>,
+            token: Secret<String>,
+        }
+
+        let outputs: Outputs = parse_outputs(br#"{
+            "ips": {
+                "sensitive": false,
+                "type": ["list", ["list", "string"]],
+                "value": [["10.0.0.1", "10.0.0.2"], []]
+            },
+            "tags": {
+                "sensitive": false,
+                "type": ["map", ["list", "string"]],
+                "value": {"env": ["prod"]}
+            },
+            "token": {
+                "sensitive": true,
+                "type": "string",
+                "value": "hunter2"
+            }
+        }"#).unwrap();
+        assert_eq!(outputs.ips, vec![vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()], vec![]]);
+        assert_eq!(outputs.tags["env"], vec!["prod".to_string()]);
+        assert_eq!(outputs.token.expose(), "hunter2");
     }
 }
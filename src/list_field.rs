@@ -1,6 +1,12 @@
 use serde::Serialize;
 use crate::{
-    TfPrimitiveType,
+    expr::{
+        each_for,
+        Expression,
+    },
+    PrimExpr,
+    PrimField,
+    PrimType,
     list_ref::{
         ListRef,
         MapListRef,
@@ -10,7 +16,7 @@ use crate::{
 
 pub enum ListField<T> {
     Literal(Vec<T>),
-    Sentinel(String),
+    Expr(Expression),
 }
 
 impl<T: Serialize> Serialize for ListField<T> {
@@ -19,7 +25,7 @@ impl<T: Serialize> Serialize for ListField<T> {
         S: serde::Serializer {
         match self {
             ListField::Literal(x) => x.serialize(serializer),
-            ListField::Sentinel(t) => t.serialize2(serializer),
+            ListField::Expr(e) => e.serialize(serializer),
         }
     }
 }
@@ -32,7 +38,7 @@ impl<T> From<Vec<T>> for ListField<T> {
 
 impl<T> From<&ListRef<T>> for ListField<T> {
     fn from(value: &ListRef<T>) -> Self {
-        Self::Sentinel(value.shared.add_sentinel(&value.base))
+        Self::Expr(value.base.clone())
     }
 }
 
@@ -42,19 +48,21 @@ impl<T> From<ListRef<T>> for ListField<T> {
     }
 }
 
+impl<T: PrimType> From<&ListRef<PrimExpr<T>>> for ListField<PrimField<T>> {
+    fn from(value: &ListRef<PrimExpr<T>>) -> Self {
+        Self::Expr(value.base.clone())
+    }
+}
+
+impl<T: PrimType> From<ListRef<PrimExpr<T>>> for ListField<PrimField<T>> {
+    fn from(value: ListRef<PrimExpr<T>>) -> Self {
+        (&value).into()
+    }
+}
+
 impl<T> From<&MapListRef<T>> for ListField<T> {
     fn from(value: &MapListRef<T>) -> Self {
-        Self::Sentinel(
-            value
-                .shared
-                .add_sentinel(
-                    &format!(
-                        "[for each in [for i, v in {}: {{ key = i, value = v }}]: {}]",
-                        value.base,
-                        value.map_base
-                    ),
-                ),
-        )
+        Self::Expr(each_for(&value.base, "i", None, value.map_base.clone()))
     }
 }
 
@@ -66,17 +74,7 @@ impl<T> From<MapListRef<T>> for ListField<T> {
 
 impl<T> From<&MapRecRefToList<T>> for ListField<T> {
     fn from(value: &MapRecRefToList<T>) -> Self {
-        Self::Sentinel(
-            value
-                .shared
-                .add_sentinel(
-                    &format!(
-                        "[for each in [for k, v in {}: {{ key = k, value = v }}]: {}]",
-                        value.base,
-                        value.map_base
-                    ),
-                ),
-        )
+        Self::Expr(each_for(&value.base, "k", None, value.map_base.clone()))
     }
 }
 
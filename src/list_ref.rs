@@ -1,12 +1,18 @@
 use std::marker::PhantomData;
 use crate::{
+    expr::{
+        each_for,
+        Expression,
+    },
     StackShared,
     prim_ref::{
         PrimExpr,
     },
     rec_ref::{
         ListToRecMappable,
+        RecRef,
     },
+    set_ref::SetRef,
     MapKV,
     Ref,
 };
@@ -14,23 +20,23 @@ use crate::{
 pub trait ToListMappable {
     type O;
 
-    fn do_map(self, base: String) -> Self::O;
+    fn do_map(self, base: Expression) -> Self::O;
 }
 
 pub trait RecToListMappable {
     type O;
 
-    fn do_map(self, base: String) -> Self::O;
+    fn do_map(self, base: Expression) -> Self::O;
 }
 
 pub struct ListRef<T> {
     pub(crate) shared: StackShared,
-    pub(crate) base: String,
+    pub(crate) base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T> Ref for ListRef<T> {
-    fn new(shared: StackShared, base: String) -> Self {
+    fn new(shared: StackShared, base: Expression) -> Self {
         ListRef {
             shared: shared,
             base: base,
@@ -41,7 +47,7 @@ impl<T> Ref for ListRef<T> {
 
 impl<T: Ref> ListRef<T> {
     pub fn get(&self, index: usize) -> T {
-        T::new(self.shared.clone(), format!("{}[{}]", &self.base, index))
+        T::new(self.shared.clone(), self.base.index(Expression::Literal(index.into())))
     }
 
     pub fn map<O: ToListMappable>(&self, inner: impl FnOnce(MapKV<T>) -> O) -> O::O {
@@ -53,17 +59,31 @@ impl<T: Ref> ListRef<T> {
         let (k, out) = inner(MapKV::new(self.shared.clone()));
         out.do_map_rec(self.base.clone(), k)
     }
+
+    /// Convert the list to a set (`toset`), ex: to create a resource per element of a
+    /// list of strings.
+    pub fn to_set(&self) -> SetRef<T> {
+        SetRef::new(self.shared.clone(), Expression::Call("toset".into(), vec![self.base.clone()]))
+    }
+
+    /// Convert the list to a record keyed by the string returned from `key` (`each.key`
+    /// is the index, `each.value` the element), ex: to create a resource per element of
+    /// a list of objects. Keys must be unique.
+    pub fn key_by(&self, key: impl FnOnce(MapKV<T>) -> PrimExpr<String>) -> RecRef<T> {
+        let k = key(MapKV::new(self.shared.clone()));
+        RecRef::new(self.shared.clone(), each_for(&self.base, "i", Some(k.1), Expression::Ref("each.value".into())))
+    }
 }
 
 pub struct MapListRef<T> {
     pub(crate) shared: StackShared,
-    pub(crate) base: String,
-    pub(crate) map_base: String,
+    pub(crate) base: Expression,
+    pub(crate) map_base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T> MapListRef<T> {
-    pub(crate) fn new(shared: StackShared, base: String, map_base: String) -> Self {
+    pub(crate) fn new(shared: StackShared, base: Expression, map_base: Expression) -> Self {
         MapListRef {
             shared: shared,
             base: base,
@@ -75,23 +95,21 @@ impl<T> MapListRef<T> {
 
 impl<T: Ref> MapListRef<T> {
     pub fn map<O: ToListMappable>(&self, inner: impl FnOnce(T) -> O) -> O::O {
-        let out = inner(T::new(self.shared.clone(), self.map_base.to_string()));
+        let out = inner(T::new(self.shared.clone(), self.map_base.clone()));
         out.do_map(self.base.clone())
     }
 }
 
 pub struct MapListRefToRec<T> {
-    pub(crate) shared: StackShared,
-    pub(crate) base: String,
-    pub(crate) map_base_key: String,
-    pub(crate) map_base: String,
+    pub(crate) base: Expression,
+    pub(crate) map_base_key: Expression,
+    pub(crate) map_base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T> MapListRefToRec<T> {
-    pub(crate) fn new(shared: StackShared, base: String, map_base_key: String, map_base: String) -> Self {
+    pub(crate) fn new(base: Expression, map_base_key: Expression, map_base: Expression) -> Self {
         MapListRefToRec {
-            shared: shared,
             base: base,
             map_base_key: map_base_key,
             map_base: map_base,
@@ -0,0 +1,47 @@
+use std::rc::Rc;
+use serde_json::Value;
+use crate::{
+    expr::Expression,
+    FieldValue,
+    Ref,
+    Stack,
+};
+
+pub(crate) trait LocalTrait {
+    fn extract_tf_id(&self) -> String;
+    fn extract_value(&self) -> Value;
+}
+
+struct Local_<V: FieldValue> {
+    tf_id: String,
+    value: V,
+}
+
+impl<V: FieldValue> LocalTrait for Local_<V> {
+    fn extract_tf_id(&self) -> String {
+        self.tf_id.clone()
+    }
+
+    fn extract_value(&self) -> Value {
+        serde_json::to_value(&self.value).unwrap()
+    }
+}
+
+/// Create a new local value. `build` returns a reference to the local
+/// (`local.<tf_id>`) of the matching type: `PrimExpr` for `PrimField`, `ListRef`
+/// for `ListField`, etc.
+pub struct BuildLocal<V: FieldValue> {
+    pub tf_id: String,
+    pub value: V,
+}
+
+impl<V: FieldValue + 'static> BuildLocal<V> {
+    pub fn build(self, stack: &mut Stack) -> V::R {
+        let out = V::R::new(stack.shared.clone(), Expression::Ref(format!("local.{}", self.tf_id)));
+        stack.locals.push(Rc::new(Local_ {
+            tf_id: self.tf_id,
+            value: self.value,
+        }));
+        out
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    rc::Rc,
+};
+use serde::Serialize;
+use serde_json::Value;
+use crate::{
+    expr::Expression,
+    FieldValue,
+    ListRef,
+    Module_,
+    PrimField,
+    RecRef,
+    RecToListMappable,
+    Ref,
+    Referable,
+    Stack,
+    StackShared,
+};
+
+#[derive(Serialize)]
+struct ModuleData {
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    providers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<PrimField<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    for_each: Option<Expression>,
+    #[serde(flatten)]
+    inputs: BTreeMap<String, Box<dyn erased_serde::Serialize>>,
+}
+
+struct ModuleCall_ {
+    shared: StackShared,
+    tf_id: String,
+    data: RefCell<ModuleData>,
+}
+
+impl Module_ for ModuleCall_ {
+    fn extract_tf_id(&self) -> String {
+        self.tf_id.clone()
+    }
+
+    fn extract_value(&self) -> Value {
+        serde_json::to_value(&*self.data.borrow()).unwrap()
+    }
+}
+
+/// A call to a Terraform module.
+#[derive(Clone)]
+pub struct Module(Rc<ModuleCall_>);
+
+impl Module {
+    /// Set the module input variable `k`.
+    pub fn set_input(self, k: impl ToString, v: impl FieldValue + 'static) -> Self {
+        self.0.data.borrow_mut().inputs.insert(k.to_string(), Box::new(v));
+        self
+    }
+
+    /// Pass a provider to the module. `k` is the provider name (with optional alias)
+    /// used within the module, `provider_ref` is the value of `provider_ref()` on a
+    /// provider in this stack.
+    pub fn set_provider(self, k: impl ToString, provider_ref: impl ToString) -> Self {
+        self.0.data.borrow_mut().providers.insert(k.to_string(), provider_ref.to_string());
+        self
+    }
+
+    pub fn depends_on(self, dep: &impl Referable) -> Self {
+        self.0.data.borrow_mut().depends_on.push(dep.extract_ref());
+        self
+    }
+
+    /// Create `v` instances of the module. Use `instances` to refer to the outputs
+    /// of each instance.
+    pub fn set_count(self, v: impl Into<PrimField<i64>>) -> Self {
+        self.0.data.borrow_mut().count = Some(v.into());
+        self
+    }
+
+    /// Get a reference to the module instances created with `set_count`.
+    pub fn instances(&self) -> ListRef<ModuleRef> {
+        ListRef::new(self.0.shared.clone(), Expression::Ref(self.extract_ref()))
+    }
+
+    /// Get a reference to the module output `name`.
+    pub fn output<R: Ref>(&self, name: &str) -> R {
+        R::new(self.0.shared.clone(), Expression::Ref(self.extract_ref()).attr(name))
+    }
+}
+
+impl Referable for Module {
+    fn extract_ref(&self) -> String {
+        format!("module.{}", self.0.tf_id)
+    }
+}
+
+impl RecToListMappable for Module {
+    type O = RecRef<ModuleRef>;
+
+    fn do_map(self, base: Expression) -> Self::O {
+        self.0.data.borrow_mut().for_each = Some(base);
+        RecRef::new(self.0.shared.clone(), Expression::Ref(self.extract_ref()))
+    }
+}
+
+/// Create a new module call. `source` is a local path or registry address, and
+/// `version` a version constraint (registry modules only).
+pub struct BuildModule {
+    pub tf_id: String,
+    pub source: String,
+    pub version: Option<String>,
+}
+
+impl BuildModule {
+    pub fn build(self, stack: &mut Stack) -> Module {
+        let out = Module(Rc::new(ModuleCall_ {
+            shared: stack.shared.clone(),
+            tf_id: self.tf_id,
+            data: RefCell::new(ModuleData {
+                source: self.source,
+                version: self.version,
+                providers: Default::default(),
+                depends_on: Default::default(),
+                count: None,
+                for_each: None,
+                inputs: Default::default(),
+            }),
+        }));
+        stack.add_module(out.0.clone());
+        out
+    }
+}
+
+/// A reference to a single instance of a module with `count` or `for_each`.
+pub struct ModuleRef {
+    shared: StackShared,
+    base: Expression,
+}
+
+impl Ref for ModuleRef {
+    fn new(shared: StackShared, base: Expression) -> Self {
+        Self {
+            shared,
+            base,
+        }
+    }
+}
+
+impl ModuleRef {
+    /// Get a reference to the module output `name`.
+    pub fn output<R: Ref>(&self, name: &str) -> R {
+        R::new(self.shared.clone(), self.base.attr(name))
+    }
+}
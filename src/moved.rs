@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+pub(crate) struct Moved {
+    pub(crate) from: String,
+    pub(crate) to: String,
+}
+
+/// Compare a previously serialized stack with a new one and suggest `moved`
+/// (from, to) address pairs for resources that were renamed: a resource that
+/// only exists in `old` and one that only exists in `new` with the same type and
+/// identical body. Resources where the match is ambiguous aren't suggested.
+///
+/// The suggestions can be passed to `Stack::add_moved_addr`.
+pub fn suggest_moved(old: &[u8], new: &[u8]) -> Result<Vec<(String, String)>, serde_json::Error> {
+    fn resources(raw: &[u8]) -> Result<BTreeMap<String, BTreeMap<String, Value>>, serde_json::Error> {
+        let stack: Value = serde_json::from_slice(raw)?;
+        match stack.get("resource") {
+            Some(r) => serde_json::from_value(r.clone()),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    let old = resources(old)?;
+    let new = resources(new)?;
+    let mut out = vec![];
+    for (resource_type, new_resources) in &new {
+        let Some(old_resources) = old.get(resource_type) else {
+            continue;
+        };
+        let removed =
+            old_resources
+                .iter()
+                .filter(|(id, _)| !new_resources.contains_key(*id))
+                .collect::<Vec<(&String, &Value)>>();
+        let added =
+            new_resources
+                .iter()
+                .filter(|(id, _)| !old_resources.contains_key(*id))
+                .collect::<Vec<(&String, &Value)>>();
+        for (new_id, new_body) in &added {
+            let mut candidates = removed.iter().filter(|(_, old_body)| old_body == new_body);
+            let (Some((old_id, _)), None) = (candidates.next(), candidates.next()) else {
+                continue;
+            };
+            if added.iter().filter(|(_, b)| b == new_body).count() != 1 {
+                continue;
+            }
+            out.push(
+                (format!("{}.{}", resource_type, old_id), format!("{}.{}", resource_type, new_id)),
+            );
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{
+        json,
+        Value,
+    };
+    use super::suggest_moved;
+
+    fn suggest(old: Value, new: Value) -> Vec<(String, String)> {
+        suggest_moved(&serde_json::to_vec(&old).unwrap(), &serde_json::to_vec(&new).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn rename() {
+        assert_eq!(suggest(json!({
+            "resource": {
+                "aws_instance": {
+                    "old": {
+                        "ami": "a"
+                    },
+                    "kept": {
+                        "ami": "b"
+                    }
+                }
+            }
+        }), json!({
+            "resource": {
+                "aws_instance": {
+                    "new": {
+                        "ami": "a"
+                    },
+                    "kept": {
+                        "ami": "b"
+                    }
+                }
+            }
+        })), vec![("aws_instance.old".to_string(), "aws_instance.new".to_string())]);
+    }
+
+    #[test]
+    fn ambiguous() {
+        // Two removed resources match the added one
+        assert_eq!(suggest(json!({
+            "resource": {
+                "aws_instance": {
+                    "a": {
+                        "ami": "x"
+                    },
+                    "b": {
+                        "ami": "x"
+                    }
+                }
+            }
+        }), json!({
+            "resource": {
+                "aws_instance": {
+                    "c": {
+                        "ami": "x"
+                    }
+                }
+            }
+        })), vec![]);
+
+        // Two added resources match the removed one
+        assert_eq!(suggest(json!({
+            "resource": {
+                "aws_instance": {
+                    "a": {
+                        "ami": "x"
+                    }
+                }
+            }
+        }), json!({
+            "resource": {
+                "aws_instance": {
+                    "b": {
+                        "ami": "x"
+                    },
+                    "c": {
+                        "ami": "x"
+                    }
+                }
+            }
+        })), vec![]);
+    }
+
+    #[test]
+    fn changed_body() {
+        assert_eq!(suggest(json!({
+            "resource": {
+                "aws_instance": {
+                    "old": {
+                        "ami": "a"
+                    }
+                }
+            }
+        }), json!({
+            "resource": {
+                "aws_instance": {
+                    "new": {
+                        "ami": "b"
+                    }
+                }
+            }
+        })), vec![]);
+    }
+
+    #[test]
+    fn different_type() {
+        assert_eq!(suggest(json!({
+            "resource": {
+                "aws_instance": {
+                    "x": {
+                        "name": "a"
+                    }
+                }
+            }
+        }), json!({
+            "resource": {
+                "aws_s3_bucket": {
+                    "y": {
+                        "name": "a"
+                    }
+                }
+            }
+        })), vec![]);
+    }
+}
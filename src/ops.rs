@@ -0,0 +1,196 @@
+use std::ops::{
+    Add,
+    Div,
+    Mul,
+    Neg,
+    Not,
+    Rem,
+    Sub,
+};
+use crate::{
+    expr::{
+        BinaryOp,
+        Expression,
+        UnaryOp,
+    },
+    PrimExpr,
+    PrimType,
+    ToFuncArg,
+};
+
+fn binary<T: PrimType, O: PrimType>(a: &PrimExpr<T>, op: BinaryOp, b: impl ToFuncArg<T>) -> PrimExpr<O> {
+    let b = b.to_func_arg(&a.0);
+    PrimExpr(a.0.clone(), Expression::Binary(op, Box::new(a.1.clone()), Box::new(b.1)), Default::default())
+}
+
+fn unary<T: PrimType>(a: &PrimExpr<T>, op: UnaryOp) -> PrimExpr<T> {
+    PrimExpr(a.0.clone(), Expression::Unary(op, Box::new(a.1.clone())), Default::default())
+}
+
+impl<T: PrimType> PrimExpr<T> {
+    /// `self == other`
+    pub fn eq(&self, other: impl ToFuncArg<T>) -> PrimExpr<bool> {
+        binary(self, BinaryOp::Eq, other)
+    }
+
+    /// `self != other`
+    pub fn ne(&self, other: impl ToFuncArg<T>) -> PrimExpr<bool> {
+        binary(self, BinaryOp::Ne, other)
+    }
+}
+
+impl PrimExpr<bool> {
+    /// `self && other`
+    pub fn and(&self, other: impl ToFuncArg<bool>) -> PrimExpr<bool> {
+        binary(self, BinaryOp::And, other)
+    }
+
+    /// `self || other`
+    pub fn or(&self, other: impl ToFuncArg<bool>) -> PrimExpr<bool> {
+        binary(self, BinaryOp::Or, other)
+    }
+}
+
+impl Not for PrimExpr<bool> {
+    type Output = PrimExpr<bool>;
+
+    fn not(self) -> Self::Output {
+        unary(&self, UnaryOp::Not)
+    }
+}
+
+impl Not for &PrimExpr<bool> {
+    type Output = PrimExpr<bool>;
+
+    fn not(self) -> Self::Output {
+        unary(self, UnaryOp::Not)
+    }
+}
+
+macro_rules! num_op{
+    ($t: ty, $o: ty, $trait: ident, $method: ident, $op: expr) => {
+        impl $trait <$t > for PrimExpr <$t > {
+            type Output = PrimExpr<$o>;
+            fn $method(self, rhs: $t) -> Self::Output {
+                binary(&self, $op, rhs)
+            }
+        }
+        impl $trait < PrimExpr <$t >> for PrimExpr <$t > {
+            type Output = PrimExpr<$o>;
+            fn $method(self, rhs: PrimExpr<$t>) -> Self::Output {
+                binary(&self, $op, rhs)
+            }
+        }
+        impl $trait <& PrimExpr <$t >> for PrimExpr <$t > {
+            type Output = PrimExpr<$o>;
+            fn $method(self, rhs: &PrimExpr<$t>) -> Self::Output {
+                binary(&self, $op, rhs)
+            }
+        }
+        impl $trait <$t > for & PrimExpr <$t > {
+            type Output = PrimExpr<$o>;
+            fn $method(self, rhs: $t) -> Self::Output {
+                binary(self, $op, rhs)
+            }
+        }
+        impl $trait < PrimExpr <$t >> for & PrimExpr <$t > {
+            type Output = PrimExpr<$o>;
+            fn $method(self, rhs: PrimExpr<$t>) -> Self::Output {
+                binary(self, $op, rhs)
+            }
+        }
+        impl $trait <& PrimExpr <$t >> for & PrimExpr <$t > {
+            type Output = PrimExpr<$o>;
+            fn $method(self, rhs: &PrimExpr<$t>) -> Self::Output {
+                binary(self, $op, rhs)
+            }
+        }
+    };
+}
+
+macro_rules! num_ops{
+    ($t: ty) => {
+        num_op!($t, $t, Add, add, BinaryOp::Add);
+        num_op!($t, $t, Sub, sub, BinaryOp::Sub);
+        num_op!($t, $t, Mul, mul, BinaryOp::Mul);
+        num_op!($t, f64, Div, div, BinaryOp::Div);
+        num_op!($t, $t, Rem, rem, BinaryOp::Rem);
+        impl Neg for PrimExpr <$t > {
+            type Output = PrimExpr<$t>;
+            fn neg(self) -> Self::Output {
+                unary(&self, UnaryOp::Neg)
+            }
+        }
+        impl Neg for & PrimExpr <$t > {
+            type Output = PrimExpr<$t>;
+            fn neg(self) -> Self::Output {
+                unary(self, UnaryOp::Neg)
+            }
+        }
+        impl PrimExpr <$t > {
+            /// `self < other`
+            pub fn lt(&self, other: impl ToFuncArg<$t>) -> PrimExpr<bool> {
+                binary(self, BinaryOp::Lt, other)
+            }
+            /// `self <= other`
+            pub fn le(&self, other: impl ToFuncArg<$t>) -> PrimExpr<bool> {
+                binary(self, BinaryOp::Le, other)
+            }
+            /// `self > other`
+            pub fn gt(&self, other: impl ToFuncArg<$t>) -> PrimExpr<bool> {
+                binary(self, BinaryOp::Gt, other)
+            }
+            /// `self >= other`
+            pub fn ge(&self, other: impl ToFuncArg<$t>) -> PrimExpr<bool> {
+                binary(self, BinaryOp::Ge, other)
+            }
+        }
+    };
+}
+
+num_ops!(i64);
+num_ops!(f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tf_if,
+        BuildStack,
+        PrimExpr,
+    };
+
+    #[test]
+    fn precedence() {
+        let stack = BuildStack::default().build();
+        let a = stack.expr_lit(1i64);
+        let b = stack.expr::<i64>("var.b");
+        assert_eq!(((&a + 2i64) * 3i64).1.render(), "(1 + 2) * 3");
+        assert_eq!((&a + &b * 3i64).1.render(), "1 + (var.b) * 3");
+        assert_eq!((&a - (&a - 2i64)).1.render(), "1 - (1 - 2)");
+        assert_eq!((-(&a + 2i64)).1.render(), "-(1 + 2)");
+        assert_eq!((&a - -2i64).1.render(), "1 - -2");
+        assert_eq!(a.lt(2i64).eq(a.gt(0i64)).1.render(), "1 < 2 == 1 > 0");
+        assert_eq!(a.eq(1i64).eq(true).1.render(), "(1 == 1) == true");
+    }
+
+    #[test]
+    fn int_div_is_float() {
+        let stack = BuildStack::default().build();
+        let n = stack.expr::<i64>("var.n");
+        let half: PrimExpr<f64> = &n / 2i64;
+        assert_eq!(half.1.render(), "(var.n) / 2");
+        let rem: PrimExpr<i64> = &n % 2i64;
+        assert_eq!(rem.1.render(), "(var.n) % 2");
+    }
+
+    #[test]
+    fn bool_ops() {
+        let stack = BuildStack::default().build();
+        let t = stack.expr_lit(true);
+        let f = stack.expr_lit(false);
+        assert_eq!(t.or(&f).and(!&f).1.render(), "(true || false) && !false");
+        assert_eq!(t.and(&f).or(&t).1.render(), "true && false || true");
+        assert_eq!(tf_if(t.or(&f), 1i64, 2i64).1.render(), "true || false ? 1 : 2");
+        assert_eq!((tf_if(&t, 1i64, 2i64) + 1i64).1.render(), "(true ? 1 : 2) + 1");
+    }
+}
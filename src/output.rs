@@ -2,6 +2,11 @@ use std::{
     cell::{
         RefCell,
     },
+    path::Path,
+    process::{
+        Command,
+        Stdio,
+    },
     rc::Rc,
 };
 use serde::{
@@ -11,10 +16,13 @@ use serde_json::{
     Value,
 };
 use crate::{
-    PrimType,
+    Condition,
     PrimField,
+    Referable,
+    RunError,
     Stack,
     SerdeSkipDefault,
+    TfValue,
 };
 
 pub(crate) trait Output {
@@ -23,26 +31,89 @@ pub(crate) trait Output {
 }
 
 #[derive(Serialize)]
-struct OutputImplData<T: PrimType> {
+struct OutputImplData<T: TfValue> {
     #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")]
     pub sensitive: PrimField<bool>,
     #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")]
-    pub value: PrimField<T>,
+    pub ephemeral: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    pub value: T::F,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub precondition: Vec<Condition>,
 }
 
-pub struct OutputImpl<T: PrimType> {
+/// An output. `T` is the Rust type of the value, ex: `String` or `Vec<String>`.
+pub struct OutputImpl<T: TfValue> {
     tf_id: String,
     data: RefCell<OutputImplData<T>>,
 }
 
-impl<T: PrimType> OutputImpl<T> {
+impl<T: TfValue> OutputImpl<T> {
     pub fn set_sensitive(&self, v: impl Into<PrimField<bool>>) -> &Self {
         self.data.borrow_mut().sensitive = v.into();
         self
     }
+
+    /// Make the output ephemeral: it's not stored in the plan or state. Only
+    /// allowed in modules, not the root stack.
+    pub fn set_ephemeral(&self, v: bool) -> &Self {
+        self.data.borrow_mut().ephemeral = v;
+        self
+    }
+
+    pub fn set_description(&self, v: impl ToString) -> &Self {
+        self.data.borrow_mut().description = Some(v.to_string());
+        self
+    }
+
+    pub fn depends_on(&self, dep: &impl Referable) -> &Self {
+        self.data.borrow_mut().depends_on.push(dep.extract_ref());
+        self
+    }
+
+    /// Fail with `error_message` before the output is evaluated if `condition` is
+    /// false.
+    pub fn add_precondition(
+        &self,
+        condition: impl Into<PrimField<bool>>,
+        error_message: impl Into<PrimField<String>>,
+    ) -> &Self {
+        self.data.borrow_mut().precondition.push(Condition {
+            condition: condition.into(),
+            error_message: error_message.into(),
+        });
+        self
+    }
 }
 
-impl<T: PrimType> Output for OutputImpl<T> {
+impl<T: TfValue> OutputImpl<T> {
+    /// Read the output's value from an applied stack. `path` is the directory in
+    /// which the .tf.json file was written. Use `Secret<T>` as the output type for
+    /// sensitive values to keep them out of logs.
+    pub fn read(&self, path: &Path) -> Result<T, RunError> {
+        let mut command = Command::new("terraform");
+        let res =
+            command
+                .current_dir(path)
+                .stderr(Stdio::inherit())
+                .args(["output", "-json", &self.tf_id])
+                .output()?;
+        if !res.status.success() {
+            return Err(RunError::CommandError(Box::new(command), res.status));
+        }
+        Ok(parse_output(&res.stdout)?)
+    }
+}
+
+/// Parse the output of `terraform output -json NAME`.
+fn parse_output<T: TfValue>(raw: &[u8]) -> Result<T, serde_json::Error> {
+    serde_json::from_slice(raw)
+}
+
+impl<T: TfValue> Output for OutputImpl<T> {
     fn extract_tf_id(&self) -> String {
         self.tf_id.clone()
     }
@@ -53,22 +124,56 @@ impl<T: PrimType> Output for OutputImpl<T> {
     }
 }
 
-/// Create a new output.
-pub struct BuildOutput<T: PrimType> {
+/// Create a new output. `value` is a field of the matching type, ex:
+/// `PrimField<String>` for `String` or `ListField<PrimField<String>>` for
+/// `Vec<String>`. Since the field type doesn't determine `T`, specify it with
+/// `BuildOutput::<Vec<String>> { ... }`. For object values, implement `TfValue` on
+/// a struct with the generated object type as `F`.
+pub struct BuildOutput<T: TfValue> {
     pub tf_id: String,
-    pub value: PrimField<T>,
+    pub value: T::F,
 }
 
-impl<T: PrimType + 'static> BuildOutput<T> {
+impl<T: TfValue + 'static> BuildOutput<T> {
     pub fn build(self, stack: &mut Stack) -> Rc<OutputImpl<T>> {
         let out = Rc::new(OutputImpl {
             tf_id: self.tf_id,
             data: RefCell::new(OutputImplData {
-                sensitive: false.into(),
+                sensitive: T::sensitive().into(),
+                ephemeral: false,
+                description: None,
+                depends_on: vec![],
                 value: self.value,
+                precondition: vec![],
             }),
         });
         stack.outputs.push(out.clone());
         out
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::Secret;
+    use super::parse_output;
+
+    #[test]
+    fn parse_nested_list() {
+        let value: Vec<Vec<String>> = parse_output(br#"[["a", "b"], ["c"]]"#).unwrap();
+        assert_eq!(value, vec![vec!["a".to_string(), "b".to_string()], vec!["c".to_string()]]);
+    }
+
+    #[test]
+    fn parse_nested_map() {
+        let value: HashMap<String, HashMap<String, i64>> = parse_output(br#"{"a": {"x": 1}, "b": {}}"#).unwrap();
+        assert_eq!(value["a"]["x"], 1);
+        assert!(value["b"].is_empty());
+    }
+
+    #[test]
+    fn parse_secret() {
+        let value: Secret<Vec<String>> = parse_output(br#"["hunter2"]"#).unwrap();
+        assert_eq!(value.expose(), &vec!["hunter2".to_string()]);
+    }
+}
@@ -1,15 +1,16 @@
-use std::{
-    fmt::Display,
-    hash::Hash,
-};
+use std::hash::Hash;
 use serde::{
     Serialize,
 };
-use crate::utils::REPLACE_EXPRS;
+use serde_json::Value;
+use crate::expr::{
+    escape_template,
+    Expression,
+};
 
 pub trait TfPrimitiveType {
     fn extract_variable_type() -> String;
-    fn to_expr_raw(&self) -> String;
+    fn to_expr(&self) -> Expression;
     fn serialize2<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer;
@@ -20,24 +21,14 @@ impl TfPrimitiveType for String {
         "string".into()
     }
 
-    fn to_expr_raw(&self) -> String {
-        return format!("\"{}\"", self.replace("\\", "\\\\").replace("\"", "\\\""));
+    fn to_expr(&self) -> Expression {
+        Expression::Literal(Value::String(self.clone()))
     }
 
     fn serialize2<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer {
-        REPLACE_EXPRS.with(|f| {
-            if let Some(vs) = f.borrow().as_ref() {
-                let mut out = self.replace("%{", "%%{").replace("${", "$${");
-                for (k, v) in vs {
-                    out = out.replace(k, v);
-                }
-                out.serialize(serializer)
-            } else {
-                self.serialize(serializer)
-            }
-        })
+        escape_template(self).serialize(serializer)
     }
 }
 
@@ -46,12 +37,8 @@ impl TfPrimitiveType for bool {
         "bool".into()
     }
 
-    fn to_expr_raw(&self) -> String {
-        if *self {
-            return "true".to_string();
-        } else {
-            return "false".to_string();
-        }
+    fn to_expr(&self) -> Expression {
+        Expression::Literal(Value::Bool(*self))
     }
 
     fn serialize2<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -63,11 +50,11 @@ impl TfPrimitiveType for bool {
 
 impl TfPrimitiveType for i64 {
     fn extract_variable_type() -> String {
-        "int".into()
+        "number".into()
     }
 
-    fn to_expr_raw(&self) -> String {
-        return self.to_string();
+    fn to_expr(&self) -> Expression {
+        Expression::Literal(Value::from(*self))
     }
 
     fn serialize2<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -79,11 +66,11 @@ impl TfPrimitiveType for i64 {
 
 impl TfPrimitiveType for f64 {
     fn extract_variable_type() -> String {
-        "float".into()
+        "number".into()
     }
 
-    fn to_expr_raw(&self) -> String {
-        return self.to_string();
+    fn to_expr(&self) -> Expression {
+        Expression::Literal(Value::from(*self))
     }
 
     fn serialize2<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -99,6 +86,13 @@ pub trait PrimType: Serialize + Clone + TfPrimitiveType + Default + PartialEq {
 
 impl<T: Serialize + Clone + TfPrimitiveType + Default + PartialEq> PrimType for T { }
 
+/// Primitive types that can hold a Terraform `number`: `i64` and `f64`.
+pub trait PrimNumber: PrimType { }
+
+impl PrimNumber for i64 { }
+
+impl PrimNumber for f64 { }
+
 /// This is a helper type, used as a final conversion to put fields in the
 /// structures before serialization.
 ///
@@ -110,7 +104,7 @@ impl<T: Serialize + Clone + TfPrimitiveType + Default + PartialEq> PrimType for
 #[derive(Clone)]
 pub enum PrimField<T: PrimType> {
     Literal(T),
-    Sentinel(String),
+    Expr(Expression),
 }
 
 impl<T: PrimType> Default for PrimField<T> {
@@ -130,7 +124,7 @@ impl<T: PrimType + PartialEq> std::cmp::PartialEq for PrimField<T> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Literal(l0), Self::Literal(r0)) => l0 == r0,
-            (Self::Sentinel(l0), Self::Sentinel(r0)) => l0 == r0,
+            (Self::Expr(l0), Self::Expr(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -144,7 +138,7 @@ impl<T: PrimType> Serialize for PrimField<T> {
         S: serde::Serializer {
         match self {
             PrimField::Literal(l) => l.serialize2(serializer),
-            PrimField::Sentinel(r) => r.serialize2(serializer),
+            PrimField::Expr(e) => e.serialize(serializer),
         }
     }
 }
@@ -167,15 +161,6 @@ impl From<&str> for PrimField<String> {
     }
 }
 
-impl<T: PrimType + Display> Display for PrimField<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            PrimField::Literal(v) => v.fmt(f),
-            PrimField::Sentinel(v) => v.fmt(f),
-        }
-    }
-}
-
 #[macro_export]
 macro_rules! primvec{
     [$($e: expr), *] => {
@@ -3,6 +3,7 @@ use crate::{
     StackShared,
     PrimType,
     Expr,
+    Expression,
     manual_expr_impls,
     prim_field::PrimField,
     list_ref::{
@@ -19,24 +20,27 @@ use crate::{
     },
     Ref,
 };
-use std::fmt::Display;
 
 /// This represents a value that can be passed to functions, put in fields, etc.
-pub struct PrimExpr<T: PrimType>(pub(crate) StackShared, pub(crate) String, pub(crate) PhantomData<T>);
+#[derive(Clone)]
+pub struct PrimExpr<T: PrimType>(pub(crate) StackShared, pub(crate) Expression, pub(crate) PhantomData<T>);
 
 impl<T: PrimType> Expr<T> for PrimExpr<T> {
-    fn expr_raw(&self) -> (&StackShared, String) {
+    fn expr(&self) -> (&StackShared, Expression) {
         (&self.0, self.1.clone())
     }
+}
 
-    fn expr_sentinel(&self) -> String {
-        self.0.add_sentinel(&self.1)
+impl<T: PrimType> From<&PrimExpr<T>> for PrimExpr<T> {
+    fn from(value: &PrimExpr<T>) -> Self {
+        value.clone()
     }
 }
 
 impl<T: PrimType> PrimExpr<T> {
+    /// The rendered expression text, for use in `stack.expr`.
     pub fn raw(&self) -> String {
-        self.1.clone()
+        self.1.render()
     }
 }
 
@@ -44,7 +48,7 @@ manual_expr_impls!(PrimExpr);
 
 // References
 impl<T: PrimType> Ref for PrimExpr<T> {
-    fn new(shared: StackShared, base: String) -> PrimExpr<T> {
+    fn new(shared: StackShared, base: Expression) -> PrimExpr<T> {
         PrimExpr(shared, base, Default::default())
     }
 }
@@ -52,7 +56,7 @@ impl<T: PrimType> Ref for PrimExpr<T> {
 impl<T: PrimType> ToListMappable for PrimExpr<T> {
     type O = MapListRef<PrimField<T>>;
 
-    fn do_map(self, base: String) -> Self::O {
+    fn do_map(self, base: Expression) -> Self::O {
         MapListRef::new(self.0, base, self.1)
     }
 }
@@ -60,23 +64,23 @@ impl<T: PrimType> ToListMappable for PrimExpr<T> {
 impl<T: PrimType> ListToRecMappable for PrimExpr<T> {
     type O = MapListRefToRec<PrimField<T>>;
 
-    fn do_map_rec(self, base: String, k: PrimExpr<String>) -> Self::O {
-        MapListRefToRec::new(self.0, base, k.1, self.1)
+    fn do_map_rec(self, base: Expression, k: PrimExpr<String>) -> Self::O {
+        MapListRefToRec::new(base, k.1, self.1)
     }
 }
 
 impl<T: PrimType> RecToListMappable for PrimExpr<T> {
     type O = MapRecRefToList<PrimField<T>>;
 
-    fn do_map(self, base: String) -> Self::O {
-        MapRecRefToList::new(self.0, base, self.1)
+    fn do_map(self, base: Expression) -> Self::O {
+        MapRecRefToList::new(base, self.1)
     }
 }
 
 impl<T: PrimType> ToObjMappable for PrimExpr<T> {
     type O = MapRecRef<PrimField<T>>;
 
-    fn do_map_rec(self, base: String, k: PrimExpr<String>) -> Self::O {
-        MapRecRef::new(self.0, base, k.1, self.1)
+    fn do_map_rec(self, base: Expression, k: PrimExpr<String>) -> Self::O {
+        MapRecRef::new(base, k.1, self.1)
     }
 }
@@ -0,0 +1,492 @@
+use serde::Serialize;
+use crate::{
+    ListField,
+    PrimField,
+    RecField,
+};
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisionerWhen {
+    Create,
+    Destroy,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisionerOnFailure {
+    Continue,
+    Fail,
+}
+
+/// A provisioner, serialized in a `provisioner` list.
+#[derive(Serialize)]
+pub enum Provisioner {
+    #[serde(rename = "local-exec")]
+    LocalExec(ProvisionerLocalExec),
+    #[serde(rename = "remote-exec")]
+    RemoteExec(ProvisionerRemoteExec),
+    #[serde(rename = "file")]
+    File(ProvisionerFile),
+}
+
+#[derive(Serialize)]
+pub struct ProvisionerLocalExec {
+    command: PrimField<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_dir: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interpreter: Option<ListField<PrimField<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<RecField<PrimField<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quiet: Option<PrimField<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when: Option<ProvisionerWhen>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_failure: Option<ProvisionerOnFailure>,
+}
+
+impl ProvisionerLocalExec {
+    pub fn set_working_dir(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.working_dir = Some(v.into());
+        self
+    }
+
+    /// Set the program and arguments used to run the command, ex: `["bash", "-c"]`.
+    pub fn set_interpreter(mut self, v: impl Into<ListField<PrimField<String>>>) -> Self {
+        self.interpreter = Some(v.into());
+        self
+    }
+
+    pub fn set_environment(mut self, v: impl Into<RecField<PrimField<String>>>) -> Self {
+        self.environment = Some(v.into());
+        self
+    }
+
+    /// Don't print the command in the Terraform output.
+    pub fn set_quiet(mut self, v: impl Into<PrimField<bool>>) -> Self {
+        self.quiet = Some(v.into());
+        self
+    }
+
+    /// Set when the provisioner runs. Provisioners run on creation by default.
+    pub fn set_when(mut self, v: ProvisionerWhen) -> Self {
+        self.when = Some(v);
+        self
+    }
+
+    pub fn set_on_failure(mut self, v: ProvisionerOnFailure) -> Self {
+        self.on_failure = Some(v);
+        self
+    }
+}
+
+impl From<ProvisionerLocalExec> for Provisioner {
+    fn from(value: ProvisionerLocalExec) -> Self {
+        Provisioner::LocalExec(value)
+    }
+}
+
+/// Run a command on the machine running Terraform.
+pub struct BuildProvisionerLocalExec {
+    pub command: PrimField<String>,
+}
+
+impl BuildProvisionerLocalExec {
+    pub fn build(self) -> ProvisionerLocalExec {
+        ProvisionerLocalExec {
+            command: self.command,
+            working_dir: None,
+            interpreter: None,
+            environment: None,
+            quiet: None,
+            when: None,
+            on_failure: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProvisionerRemoteExec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inline: Option<ListField<PrimField<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    script: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scripts: Option<ListField<PrimField<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection: Option<Connection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when: Option<ProvisionerWhen>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_failure: Option<ProvisionerOnFailure>,
+}
+
+impl ProvisionerRemoteExec {
+    /// Overrides the resource's connection.
+    pub fn set_connection(mut self, v: Connection) -> Self {
+        self.connection = Some(v);
+        self
+    }
+
+    /// Set when the provisioner runs. Provisioners run on creation by default.
+    pub fn set_when(mut self, v: ProvisionerWhen) -> Self {
+        self.when = Some(v);
+        self
+    }
+
+    pub fn set_on_failure(mut self, v: ProvisionerOnFailure) -> Self {
+        self.on_failure = Some(v);
+        self
+    }
+}
+
+impl From<ProvisionerRemoteExec> for Provisioner {
+    fn from(value: ProvisionerRemoteExec) -> Self {
+        Provisioner::RemoteExec(value)
+    }
+}
+
+/// What `remote-exec` runs on the remote machine.
+pub enum RemoteExecCommands {
+    /// A list of commands, run in order.
+    Inline(ListField<PrimField<String>>),
+    /// A local script, copied to the remote machine and run.
+    Script(PrimField<String>),
+    /// A list of local scripts, copied to the remote machine and run in order.
+    Scripts(ListField<PrimField<String>>),
+}
+
+/// Run commands on the provisioned machine, using the resource's connection.
+pub struct BuildProvisionerRemoteExec {
+    pub commands: RemoteExecCommands,
+}
+
+impl BuildProvisionerRemoteExec {
+    pub fn build(self) -> ProvisionerRemoteExec {
+        let mut out = ProvisionerRemoteExec {
+            inline: None,
+            script: None,
+            scripts: None,
+            connection: None,
+            when: None,
+            on_failure: None,
+        };
+        match self.commands {
+            RemoteExecCommands::Inline(v) => out.inline = Some(v),
+            RemoteExecCommands::Script(v) => out.script = Some(v),
+            RemoteExecCommands::Scripts(v) => out.scripts = Some(v),
+        }
+        out
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProvisionerFile {
+    destination: PrimField<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection: Option<Connection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when: Option<ProvisionerWhen>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_failure: Option<ProvisionerOnFailure>,
+}
+
+impl ProvisionerFile {
+    /// Overrides the resource's connection.
+    pub fn set_connection(mut self, v: Connection) -> Self {
+        self.connection = Some(v);
+        self
+    }
+
+    /// Set when the provisioner runs. Provisioners run on creation by default.
+    pub fn set_when(mut self, v: ProvisionerWhen) -> Self {
+        self.when = Some(v);
+        self
+    }
+
+    pub fn set_on_failure(mut self, v: ProvisionerOnFailure) -> Self {
+        self.on_failure = Some(v);
+        self
+    }
+}
+
+impl From<ProvisionerFile> for Provisioner {
+    fn from(value: ProvisionerFile) -> Self {
+        Provisioner::File(value)
+    }
+}
+
+/// What the `file` provisioner copies.
+pub enum FileSource {
+    /// A local file or directory path.
+    Path(PrimField<String>),
+    /// Literal file contents.
+    Content(PrimField<String>),
+}
+
+/// Copy a file or directory to the provisioned machine, using the resource's
+/// connection.
+pub struct BuildProvisionerFile {
+    pub source: FileSource,
+    pub destination: PrimField<String>,
+}
+
+impl BuildProvisionerFile {
+    pub fn build(self) -> ProvisionerFile {
+        let mut out = ProvisionerFile {
+            destination: self.destination,
+            source: None,
+            content: None,
+            connection: None,
+            when: None,
+            on_failure: None,
+        };
+        match self.source {
+            FileSource::Path(v) => out.source = Some(v),
+            FileSource::Content(v) => out.content = Some(v),
+        }
+        out
+    }
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ConnectionType {
+    Ssh,
+    Winrm,
+}
+
+/// How `remote-exec` and `file` provisioners connect to the machine. Build with
+/// `BuildConnectionSsh` or `BuildConnectionWinrm`.
+#[derive(Serialize)]
+pub struct Connection {
+    r#type: ConnectionType,
+    host: PrimField<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<PrimField<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    script_path: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_key: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    certificate: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent: Option<PrimField<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent_identity: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_key: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_platform: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bastion_host: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bastion_host_key: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bastion_port: Option<PrimField<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bastion_user: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bastion_password: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bastion_private_key: Option<PrimField<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    https: Option<PrimField<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    insecure: Option<PrimField<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_ntlm: Option<PrimField<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cacert: Option<PrimField<String>>,
+}
+
+impl Connection {
+    fn new(r#type: ConnectionType, host: PrimField<String>) -> Self {
+        Connection {
+            r#type,
+            host,
+            user: None,
+            password: None,
+            port: None,
+            timeout: None,
+            script_path: None,
+            private_key: None,
+            certificate: None,
+            agent: None,
+            agent_identity: None,
+            host_key: None,
+            target_platform: None,
+            bastion_host: None,
+            bastion_host_key: None,
+            bastion_port: None,
+            bastion_user: None,
+            bastion_password: None,
+            bastion_private_key: None,
+            https: None,
+            insecure: None,
+            use_ntlm: None,
+            cacert: None,
+        }
+    }
+
+    pub fn set_user(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.user = Some(v.into());
+        self
+    }
+
+    pub fn set_password(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.password = Some(v.into());
+        self
+    }
+
+    pub fn set_port(mut self, v: impl Into<PrimField<i64>>) -> Self {
+        self.port = Some(v.into());
+        self
+    }
+
+    /// Set how long to wait for the connection, ex: `5m`.
+    pub fn set_timeout(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.timeout = Some(v.into());
+        self
+    }
+
+    /// Set the remote path scripts are copied to before running.
+    pub fn set_script_path(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.script_path = Some(v.into());
+        self
+    }
+
+    /// SSH only. Set the contents of the private key.
+    pub fn set_private_key(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.private_key = Some(v.into());
+        self
+    }
+
+    /// SSH only. Set the contents of a signed CA certificate used with the private
+    /// key.
+    pub fn set_certificate(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.certificate = Some(v.into());
+        self
+    }
+
+    /// SSH only. Use the local SSH agent to authenticate.
+    pub fn set_agent(mut self, v: impl Into<PrimField<bool>>) -> Self {
+        self.agent = Some(v.into());
+        self
+    }
+
+    /// SSH only.
+    pub fn set_agent_identity(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.agent_identity = Some(v.into());
+        self
+    }
+
+    /// SSH only. Set the public key of the host, to verify the host.
+    pub fn set_host_key(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.host_key = Some(v.into());
+        self
+    }
+
+    /// SSH only. `unix` or `windows`.
+    pub fn set_target_platform(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.target_platform = Some(v.into());
+        self
+    }
+
+    /// SSH only. Connect to the host through this bastion host.
+    pub fn set_bastion_host(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.bastion_host = Some(v.into());
+        self
+    }
+
+    /// SSH only.
+    pub fn set_bastion_host_key(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.bastion_host_key = Some(v.into());
+        self
+    }
+
+    /// SSH only.
+    pub fn set_bastion_port(mut self, v: impl Into<PrimField<i64>>) -> Self {
+        self.bastion_port = Some(v.into());
+        self
+    }
+
+    /// SSH only.
+    pub fn set_bastion_user(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.bastion_user = Some(v.into());
+        self
+    }
+
+    /// SSH only.
+    pub fn set_bastion_password(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.bastion_password = Some(v.into());
+        self
+    }
+
+    /// SSH only.
+    pub fn set_bastion_private_key(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.bastion_private_key = Some(v.into());
+        self
+    }
+
+    /// WinRM only. Connect using HTTPS instead of HTTP.
+    pub fn set_https(mut self, v: impl Into<PrimField<bool>>) -> Self {
+        self.https = Some(v.into());
+        self
+    }
+
+    /// WinRM only. Don't verify the HTTPS certificate.
+    pub fn set_insecure(mut self, v: impl Into<PrimField<bool>>) -> Self {
+        self.insecure = Some(v.into());
+        self
+    }
+
+    /// WinRM only. Use NTLM authentication.
+    pub fn set_use_ntlm(mut self, v: impl Into<PrimField<bool>>) -> Self {
+        self.use_ntlm = Some(v.into());
+        self
+    }
+
+    /// WinRM only. Set the CA certificate contents used to verify the HTTPS
+    /// certificate.
+    pub fn set_cacert(mut self, v: impl Into<PrimField<String>>) -> Self {
+        self.cacert = Some(v.into());
+        self
+    }
+}
+
+/// Connect to the machine with SSH. Use the resource's `self_ref()` to refer to
+/// the machine's attributes, ex: the host ip.
+pub struct BuildConnectionSsh {
+    pub host: PrimField<String>,
+}
+
+impl BuildConnectionSsh {
+    pub fn build(self) -> Connection {
+        Connection::new(ConnectionType::Ssh, self.host)
+    }
+}
+
+/// Connect to the machine with WinRM. Use the resource's `self_ref()` to refer to
+/// the machine's attributes, ex: the host ip.
+pub struct BuildConnectionWinrm {
+    pub host: PrimField<String>,
+}
+
+impl BuildConnectionWinrm {
+    pub fn build(self) -> Connection {
+        Connection::new(ConnectionType::Winrm, self.host)
+    }
+}
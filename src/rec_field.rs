@@ -1,7 +1,13 @@
 use std::collections::HashMap;
 use serde::Serialize;
 use crate::{
-    TfPrimitiveType,
+    expr::{
+        each_for,
+        Expression,
+    },
+    PrimExpr,
+    PrimField,
+    PrimType,
     rec_ref::{
         RecRef,
         MapRecRef,
@@ -12,7 +18,7 @@ use crate::{
 
 pub enum RecField<T> {
     Literal(HashMap<String, T>),
-    Sentinel(String),
+    Expr(Expression),
 }
 
 impl<T: Serialize> Serialize for RecField<T> {
@@ -21,7 +27,7 @@ impl<T: Serialize> Serialize for RecField<T> {
         S: serde::Serializer {
         match self {
             RecField::Literal(x) => x.serialize(serializer),
-            RecField::Sentinel(t) => t.serialize2(serializer),
+            RecField::Expr(e) => e.serialize(serializer),
         }
     }
 }
@@ -34,40 +40,24 @@ impl<T> From<HashMap<String, T>> for RecField<T> {
 
 impl<T: Ref> From<&RecRef<T>> for RecField<T> {
     fn from(value: &RecRef<T>) -> Self {
-        Self::Sentinel(value.shared.add_sentinel(&value.base))
+        Self::Expr(value.base.clone())
+    }
+}
+
+impl<T: PrimType> From<&RecRef<PrimExpr<T>>> for RecField<PrimField<T>> {
+    fn from(value: &RecRef<PrimExpr<T>>) -> Self {
+        Self::Expr(value.base.clone())
     }
 }
 
 impl<T: Ref> From<&MapRecRef<T>> for RecField<T> {
     fn from(value: &MapRecRef<T>) -> Self {
-        Self::Sentinel(
-            value
-                .shared
-                .add_sentinel(
-                    &format!(
-                        "{{for each in [for k, v in {}: {{ key = k, value = v }}]: {} => {}}}",
-                        value.base,
-                        value.map_base_key,
-                        value.map_base
-                    ),
-                ),
-        )
+        Self::Expr(each_for(&value.base, "k", Some(value.map_base_key.clone()), value.map_base.clone()))
     }
 }
 
 impl<T: Ref> From<&MapListRefToRec<T>> for RecField<T> {
     fn from(value: &MapListRefToRec<T>) -> Self {
-        Self::Sentinel(
-            value
-                .shared
-                .add_sentinel(
-                    &format!(
-                        "{{for each in [for i, v in {}: {{ key = i, value = v }}]: {} => {}}}",
-                        value.base,
-                        value.map_base_key,
-                        value.map_base
-                    ),
-                ),
-        )
+        Self::Expr(each_for(&value.base, "i", Some(value.map_base_key.clone()), value.map_base.clone()))
     }
 }
@@ -1,5 +1,6 @@
 use std::marker::PhantomData;
 use crate::{
+    expr::Expression,
     prim_ref::{
         PrimExpr,
     },
@@ -14,23 +15,23 @@ use crate::{
 pub trait ToObjMappable {
     type O;
 
-    fn do_map_rec(self, base: String, k: PrimExpr<String>) -> Self::O;
+    fn do_map_rec(self, base: Expression, k: PrimExpr<String>) -> Self::O;
 }
 
 pub trait ListToRecMappable {
     type O;
 
-    fn do_map_rec(self, base: String, k: PrimExpr<String>) -> Self::O;
+    fn do_map_rec(self, base: Expression, k: PrimExpr<String>) -> Self::O;
 }
 
 pub struct RecRef<T: Ref> {
     pub(crate) shared: StackShared,
-    pub(crate) base: String,
+    pub(crate) base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T: Ref> Ref for RecRef<T> {
-    fn new(shared: StackShared, base: String) -> Self {
+    fn new(shared: StackShared, base: Expression) -> Self {
         RecRef {
             shared: shared,
             base: base,
@@ -41,7 +42,7 @@ impl<T: Ref> Ref for RecRef<T> {
 
 impl<T: Ref> RecRef<T> {
     pub fn get(&self, key: impl ToString) -> T {
-        T::new(self.shared.clone(), format!("{}[\"{}\"]", &self.base, key.to_string()))
+        T::new(self.shared.clone(), self.base.index(Expression::Literal(key.to_string().into())))
     }
 
     pub fn map<O: RecToListMappable>(&self, inner: impl FnOnce(MapKV<T>) -> O) -> O::O {
@@ -56,17 +57,15 @@ impl<T: Ref> RecRef<T> {
 }
 
 pub struct MapRecRef<T> {
-    pub(crate) shared: StackShared,
-    pub(crate) base: String,
-    pub(crate) map_base_key: String,
-    pub(crate) map_base: String,
+    pub(crate) base: Expression,
+    pub(crate) map_base_key: Expression,
+    pub(crate) map_base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T> MapRecRef<T> {
-    pub(crate) fn new(shared: StackShared, base: String, map_base_key: String, map_base: String) -> Self {
+    pub(crate) fn new(base: Expression, map_base_key: Expression, map_base: Expression) -> Self {
         MapRecRef {
-            shared: shared,
             base: base,
             map_base_key: map_base_key,
             map_base: map_base,
@@ -76,16 +75,14 @@ impl<T> MapRecRef<T> {
 }
 
 pub struct MapRecRefToList<T> {
-    pub(crate) shared: StackShared,
-    pub(crate) base: String,
-    pub(crate) map_base: String,
+    pub(crate) base: Expression,
+    pub(crate) map_base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T> MapRecRefToList<T> {
-    pub(crate) fn new(shared: StackShared, base: String, map_base: String) -> Self {
+    pub(crate) fn new(base: Expression, map_base: Expression) -> Self {
         MapRecRefToList {
-            shared: shared,
             base: base,
             map_base: map_base,
             _pd: Default::default(),
@@ -1,5 +1,150 @@
-use crate::StackShared;
+use serde::Serialize;
+use serde_json::Value;
+use crate::{
+    ListField,
+    ListRef,
+    PrimExpr,
+    PrimField,
+    PrimType,
+    RecField,
+    RecRef,
+    SetField,
+    SetRef,
+    StackShared,
+    expr::{
+        Expression,
+        NotLiteral,
+    },
+};
 
 pub trait Ref {
-    fn new(shared: StackShared, base: String) -> Self;
+    fn new(shared: StackShared, base: Expression) -> Self;
+}
+
+/// References to attributes, usable as attribute paths in resource lifecycle
+/// settings (`ignore_changes`, `replace_triggered_by`).
+pub trait AttrPath {
+    /// The root reference (ex: `aws_instance.x`) and the attribute path after it (ex:
+    /// `.tags["k"]`), or `None` if this isn't a plain attribute reference.
+    fn extract_attr_path(&self) -> Option<(String, String)>;
+}
+
+/// Split `e` into its root reference and the attribute and literal index accesses
+/// after it. Returns `None` for anything else (function calls, operators, etc.),
+/// which can't be used as attribute paths.
+pub fn split_attr_path(e: &Expression) -> Option<(String, String)> {
+    match e {
+        Expression::Ref(r) => Some((r.clone(), String::new())),
+        Expression::Attr(base, name) => {
+            let (root, path) = split_attr_path(base)?;
+            Some((root, format!("{}.{}", path, name)))
+        },
+        Expression::Index(base, index) if matches!(**index, Expression::Literal(_)) => {
+            let (root, path) = split_attr_path(base)?;
+            Some((root, format!("{}[{}]", path, index.render())))
+        },
+        _ => None,
+    }
+}
+
+impl<T: PrimType> AttrPath for PrimExpr<T> {
+    fn extract_attr_path(&self) -> Option<(String, String)> {
+        split_attr_path(&self.1)
+    }
+}
+
+impl<T> AttrPath for ListRef<T> {
+    fn extract_attr_path(&self) -> Option<(String, String)> {
+        split_attr_path(&self.base)
+    }
+}
+
+impl<T: Ref> AttrPath for SetRef<T> {
+    fn extract_attr_path(&self) -> Option<(String, String)> {
+        split_attr_path(&self.base)
+    }
+}
+
+impl<T: Ref> AttrPath for RecRef<T> {
+    fn extract_attr_path(&self) -> Option<(String, String)> {
+        split_attr_path(&self.base)
+    }
+}
+
+/// Field values that can be assigned as a whole (to locals, module inputs, etc.),
+/// with the reference type used to read the value back.
+pub trait FieldValue: Serialize {
+    type R: Ref;
+
+    /// The Terraform type constraint, ex: `list(string)`.
+    fn extract_variable_type() -> String;
+
+    /// The value as plain json, without template escaping, for places that only
+    /// accept literal values (ex: variable defaults). Errors if any part of the value
+    /// is an expression.
+    fn to_literal(&self) -> Result<Value, NotLiteral>;
+}
+
+impl<T: PrimType> FieldValue for PrimField<T> {
+    type R = PrimExpr<T>;
+
+    fn extract_variable_type() -> String {
+        T::extract_variable_type()
+    }
+
+    fn to_literal(&self) -> Result<Value, NotLiteral> {
+        match self {
+            PrimField::Literal(l) => Ok(serde_json::to_value(l).unwrap()),
+            PrimField::Expr(e) => Err(NotLiteral(e.clone())),
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for ListField<T> {
+    type R = ListRef<T::R>;
+
+    fn extract_variable_type() -> String {
+        format!("list({})", T::extract_variable_type())
+    }
+
+    fn to_literal(&self) -> Result<Value, NotLiteral> {
+        match self {
+            ListField::Literal(x) => Ok(Value::Array(x.iter().map(|v| v.to_literal()).collect::<Result<_, _>>()?)),
+            ListField::Expr(e) => Err(NotLiteral(e.clone())),
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for SetField<T> {
+    type R = SetRef<T::R>;
+
+    fn extract_variable_type() -> String {
+        format!("set({})", T::extract_variable_type())
+    }
+
+    fn to_literal(&self) -> Result<Value, NotLiteral> {
+        match self {
+            SetField::Literal(x) => Ok(Value::Array(x.iter().map(|v| v.to_literal()).collect::<Result<_, _>>()?)),
+            SetField::Expr(e) => Err(NotLiteral(e.clone())),
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for RecField<T> {
+    type R = RecRef<T::R>;
+
+    fn extract_variable_type() -> String {
+        format!("map({})", T::extract_variable_type())
+    }
+
+    fn to_literal(&self) -> Result<Value, NotLiteral> {
+        match self {
+            RecField::Literal(x) => Ok(
+                Value::Object(
+                    x.iter().map(|(k, v)| Ok((k.clone(), v.to_literal()?))).collect::<Result<_, NotLiteral>>()?,
+                ),
+            ),
+            RecField::Expr(e) => Err(NotLiteral(e.clone())),
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+use serde::Serialize;
+use serde_json::Value;
+use crate::{
+    Connection,
+    Provisioner,
+};
+
+#[derive(Serialize)]
+struct RemovedLifecycle {
+    destroy: bool,
+}
+
+#[derive(Serialize)]
+struct RemovedData {
+    from: String,
+    lifecycle: RemovedLifecycle,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection: Option<Connection>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    provisioner: Vec<Provisioner>,
+}
+
+pub(crate) struct Removed_ {
+    data: RefCell<RemovedData>,
+}
+
+impl Removed_ {
+    pub(crate) fn new(from: String, destroy: bool) -> Self {
+        Removed_ {
+            data: RefCell::new(RemovedData {
+                from,
+                lifecycle: RemovedLifecycle { destroy },
+                connection: None,
+                provisioner: vec![],
+            }),
+        }
+    }
+
+    pub(crate) fn extract_value(&self) -> Value {
+        serde_json::to_value(&*self.data.borrow()).unwrap()
+    }
+}
+
+/// A resource or module that was removed from the stack.
+pub struct Removed(pub(crate) Rc<Removed_>);
+
+impl Removed {
+    /// Add a provisioner to run when the removed resource is destroyed. Only applies
+    /// if `destroy` is true, and the provisioner must have `when` set to
+    /// `ProvisionerWhen::Destroy`.
+    pub fn add_provisioner(self, v: impl Into<Provisioner>) -> Self {
+        self.0.data.borrow_mut().provisioner.push(v.into());
+        self
+    }
+
+    /// Set the default connection for `remote-exec` and `file` provisioners.
+    pub fn set_connection(self, v: Connection) -> Self {
+        self.0.data.borrow_mut().connection = Some(v);
+        self
+    }
+}
@@ -1,13 +1,20 @@
 use serde::Serialize;
 use crate::{
-    TfPrimitiveType,
+    expr::{
+        each_for,
+        Expression,
+    },
+    PrimExpr,
+    PrimField,
+    PrimType,
+    SetRef,
     list_ref::MapListRef,
     rec_ref::MapRecRefToList,
 };
 
 pub enum SetField<T> {
     Literal(Vec<T>),
-    Sentinel(String),
+    Expr(Expression),
 }
 
 impl<T: Serialize> Serialize for SetField<T> {
@@ -16,7 +23,7 @@ impl<T: Serialize> Serialize for SetField<T> {
         S: serde::Serializer {
         match self {
             SetField::Literal(x) => x.serialize(serializer),
-            SetField::Sentinel(t) => t.serialize2(serializer),
+            SetField::Expr(e) => e.serialize(serializer),
         }
     }
 }
@@ -27,19 +34,15 @@ impl<T> From<Vec<T>> for SetField<T> {
     }
 }
 
+impl<T: PrimType> From<&SetRef<PrimExpr<T>>> for SetField<PrimField<T>> {
+    fn from(value: &SetRef<PrimExpr<T>>) -> Self {
+        Self::Expr(value.base.clone())
+    }
+}
+
 impl<T> From<&MapListRef<T>> for SetField<T> {
     fn from(value: &MapListRef<T>) -> Self {
-        Self::Sentinel(
-            value
-                .shared
-                .add_sentinel(
-                    &format!(
-                        "toset([for each in [for i, v in {}: {{ key = i, value = v }}]: {}])",
-                        value.base,
-                        value.map_base
-                    ),
-                ),
-        )
+        Self::Expr(Expression::Call("toset".into(), vec![each_for(&value.base, "i", None, value.map_base.clone())]))
     }
 }
 
@@ -51,17 +54,7 @@ impl<T> From<MapListRef<T>> for SetField<T> {
 
 impl<T> From<&MapRecRefToList<T>> for SetField<T> {
     fn from(value: &MapRecRefToList<T>) -> Self {
-        Self::Sentinel(
-            value
-                .shared
-                .add_sentinel(
-                    &format!(
-                        "toset([for each in [for k, v in {}: {{ key = k, value = v }}]: {}])",
-                        value.base,
-                        value.map_base
-                    ),
-                ),
-        )
+        Self::Expr(Expression::Call("toset".into(), vec![each_for(&value.base, "k", None, value.map_base.clone())]))
     }
 }
 
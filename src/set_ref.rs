@@ -1,8 +1,9 @@
 use std::marker::PhantomData;
 use crate::{
+    expr::Expression,
     StackShared,
     ref_::Ref,
-    list_ref::ToListMappable,
+    list_ref::RecToListMappable,
     MapKV,
     ListRef,
 };
@@ -10,12 +11,12 @@ use crate::{
 // Implemented by things that can be mapped from a set data source
 pub struct SetRef<T: Ref> {
     pub(crate) shared: StackShared,
-    pub(crate) base: String,
+    pub(crate) base: Expression,
     _pd: PhantomData<T>,
 }
 
 impl<T: Ref> Ref for SetRef<T> {
-    fn new(shared: StackShared, base: String) -> Self {
+    fn new(shared: StackShared, base: Expression) -> Self {
         SetRef {
             shared: shared,
             base: base,
@@ -25,12 +26,15 @@ impl<T: Ref> Ref for SetRef<T> {
 }
 
 impl<T: Ref> SetRef<T> {
-    pub fn map<O: ToListMappable>(&self, inner: impl FnOnce(MapKV<T>) -> O) -> O::O {
+    /// Like `RecRef::map`, where both `each.key` and `each.value` are the element.
+    /// Defining a resource in `inner` creates an instance per element, which requires
+    /// a set of strings.
+    pub fn map<O: RecToListMappable>(&self, inner: impl FnOnce(MapKV<T>) -> O) -> O::O {
         let out = inner(MapKV::new(self.shared.clone()));
         out.do_map(self.base.clone())
     }
 
     pub fn as_list(self) -> ListRef<T> {
-        ListRef::new(self.shared, format!("tolist({})", self.base))
+        ListRef::new(self.shared, Expression::Call("tolist".into(), vec![self.base]))
     }
 }
@@ -0,0 +1,340 @@
+use crate::{
+    expr::{
+        Expr,
+        Expression,
+        Strip,
+        TemplateFor,
+        TemplateIf,
+        TemplatePart,
+    },
+    ListRef,
+    PrimExpr,
+    PrimField,
+    PrimType,
+    RecRef,
+    Ref,
+    StackShared,
+    ToArg,
+    Variable,
+};
+
+/// Values that can be put in a template: primitives and strings become escaped
+/// literal text, expressions become interpolations.
+pub trait ToTemplatePart {
+    fn to_template_part(self) -> TemplatePart;
+}
+
+impl<T: PrimType + ToString> ToTemplatePart for T {
+    fn to_template_part(self) -> TemplatePart {
+        TemplatePart::Lit(self.to_string())
+    }
+}
+
+impl ToTemplatePart for &str {
+    fn to_template_part(self) -> TemplatePart {
+        TemplatePart::Lit(self.to_string())
+    }
+}
+
+impl<T: PrimType> ToTemplatePart for PrimExpr<T> {
+    fn to_template_part(self) -> TemplatePart {
+        TemplatePart::Interp(self.1)
+    }
+}
+
+impl<T: PrimType> ToTemplatePart for &PrimExpr<T> {
+    fn to_template_part(self) -> TemplatePart {
+        TemplatePart::Interp(self.1.clone())
+    }
+}
+
+impl<T: PrimType + ToString> ToTemplatePart for PrimField<T> {
+    fn to_template_part(self) -> TemplatePart {
+        match self {
+            PrimField::Literal(v) => TemplatePart::Lit(v.to_string()),
+            PrimField::Expr(e) => TemplatePart::Interp(e),
+        }
+    }
+}
+
+impl<T: PrimType> ToTemplatePart for &Variable<T> {
+    fn to_template_part(self) -> TemplatePart {
+        TemplatePart::Interp(self.expr().1)
+    }
+}
+
+/// Builds a string template expression, the replacement for `format!` with
+/// expressions. Start one with `stack.template()` or use the `tfformat!` macro.
+pub struct TemplateBuilder {
+    pub(crate) shared: StackShared,
+    pub(crate) parts: Vec<TemplatePart>,
+    pub(crate) strip: Strip,
+}
+
+impl TemplateBuilder {
+    /// Add literal text, escaped as necessary.
+    pub fn lit(mut self, v: impl ToString) -> Self {
+        self.parts.push(TemplatePart::Lit(v.to_string()));
+        self
+    }
+
+    /// Add an interpolated value (`${...}`).
+    pub fn expr(mut self, v: impl ToTemplatePart) -> Self {
+        self.parts.push(v.to_template_part());
+        self
+    }
+
+    /// Add text following a `format!`-style pattern, replacing each `{}` with the
+    /// next element of `args`. `{{` and `}}` produce literal braces.
+    ///
+    /// Panics if the number of `{}` doesn't match the number of `args`.
+    pub fn format(mut self, pattern: &str, args: Vec<TemplatePart>) -> Self {
+        let mut args = args.into_iter();
+        let mut lit = String::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    lit.push(c);
+                },
+                ('{', Some('}')) => {
+                    chars.next();
+                    if !lit.is_empty() {
+                        self.parts.push(TemplatePart::Lit(std::mem::take(&mut lit)));
+                    }
+                    self.parts.push(args.next().expect("Fewer template arguments than placeholders"));
+                },
+                _ => lit.push(c),
+            }
+        }
+        if !lit.is_empty() {
+            self.parts.push(TemplatePart::Lit(lit));
+        }
+        if args.next().is_some() {
+            panic!("More template arguments than placeholders");
+        }
+        self
+    }
+
+    /// Like `format`, but first removes the common leading indentation from the lines
+    /// of `pattern` (like a `<<-EOT` heredoc) along with a leading newline, so
+    /// multiline templates can be written indented in Rust source.
+    pub fn heredoc(self, pattern: &str, args: Vec<TemplatePart>) -> Self {
+        self.format(&dedent(pattern), args)
+    }
+
+    /// Add strip markers (`~`) to the tags of directives added after this, to remove
+    /// whitespace before and/or after them. Ex: with `after` set, the newline
+    /// following a `%{ for ... }` line in a heredoc isn't repeated for each element.
+    pub fn set_strip(mut self, before: bool, after: bool) -> Self {
+        self.strip = Strip {
+            before,
+            after,
+        };
+        self
+    }
+
+    fn nested(&self) -> TemplateBuilder {
+        TemplateBuilder {
+            shared: self.shared.clone(),
+            parts: vec![],
+            strip: self.strip,
+        }
+    }
+
+    /// Add an `%{ if cond }` directive, with contents added to the builder in `then`.
+    pub fn if_then(
+        self,
+        cond: impl Into<PrimExpr<bool>>,
+        then: impl FnOnce(TemplateBuilder) -> TemplateBuilder,
+    ) -> Self {
+        self.if_then_else(cond, then, |b| b)
+    }
+
+    /// Add an `%{ if cond }` directive with an `%{ else }` branch.
+    pub fn if_then_else(
+        mut self,
+        cond: impl Into<PrimExpr<bool>>,
+        then: impl FnOnce(TemplateBuilder) -> TemplateBuilder,
+        else_: impl FnOnce(TemplateBuilder) -> TemplateBuilder,
+    ) -> Self {
+        let then = then(self.nested()).parts;
+        let else_ = else_(self.nested()).parts;
+        self.parts.push(TemplatePart::If(Box::new(TemplateIf {
+            cond: cond.into().1,
+            then,
+            else_,
+            strip: self.strip,
+        })));
+        self
+    }
+
+    /// Add a `%{ for v in list }` directive, repeating the contents added to the
+    /// builder in `body` for each element. `body` also gets a reference to the
+    /// element. Sets can be iterated too.
+    pub fn for_in<T: Ref>(mut self, list: impl ToArg<ListRef<T>>, body: impl FnOnce(TemplateBuilder, T) -> TemplateBuilder) -> Self {
+        let value_var = format!("v{}", self.shared.next_loop_var());
+        let body = body(self.nested(), T::new(self.shared.clone(), Expression::Ref(value_var.clone()))).parts;
+        self.parts.push(TemplatePart::For(Box::new(TemplateFor {
+            key_var: None,
+            value_var,
+            collection: list.to_arg(),
+            body,
+            strip: self.strip,
+        })));
+        self
+    }
+
+    /// Add a `%{ for k, v in rec }` directive, repeating the contents added to the
+    /// builder in `body` for each key and element.
+    pub fn for_in_rec<T: Ref>(
+        mut self,
+        rec: impl ToArg<RecRef<T>>,
+        body: impl FnOnce(TemplateBuilder, PrimExpr<String>, T) -> TemplateBuilder,
+    ) -> Self {
+        let i = self.shared.next_loop_var();
+        let key_var = format!("k{}", i);
+        let value_var = format!("v{}", i);
+        let body =
+            body(
+                self.nested(),
+                PrimExpr::new(self.shared.clone(), Expression::Ref(key_var.clone())),
+                T::new(self.shared.clone(), Expression::Ref(value_var.clone())),
+            ).parts;
+        self.parts.push(TemplatePart::For(Box::new(TemplateFor {
+            key_var: Some(key_var),
+            value_var,
+            collection: rec.to_arg(),
+            body,
+            strip: self.strip,
+        })));
+        self
+    }
+
+    pub fn build(self) -> PrimExpr<String> {
+        PrimExpr(self.shared, Expression::Template(self.parts), Default::default())
+    }
+}
+
+/// Remove a leading newline and the indentation (spaces and tabs) common to all
+/// non-blank lines.
+fn dedent(text: &str) -> String {
+    let text = text.strip_prefix('\n').unwrap_or(text);
+    let indent =
+        text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start_matches([' ', '\t']).len())
+            .min()
+            .unwrap_or(0);
+    let mut out = vec![];
+    for line in text.split('\n') {
+        if line.trim().is_empty() {
+            out.push("");
+        } else {
+            out.push(&line[indent..]);
+        }
+    }
+    out.join("\n")
+}
+
+/// `format!` for Terraform templates: `tfformat!(stack, "{}-{}", my_expr, "text")`
+/// produces a `PrimExpr<String>` where expressions are interpolated and everything
+/// else is escaped literal text.
+#[macro_export]
+macro_rules! tfformat{
+    ($stack: expr, $pattern: expr $(, $e: expr) * $(,) ?) => {
+        $stack.template().format($pattern, vec![$(terrars::ToTemplatePart::to_template_part($e)), *]).build()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::{
+        dedent,
+        ToTemplatePart,
+    };
+    use crate::{
+        BuildStack,
+        ListRef,
+        PrimExpr,
+        Ref,
+        expr::Expression,
+    };
+
+    #[test]
+    fn dedent_common_indent() {
+        assert_eq!(dedent("\n    a\n      b\n\n    c\n"), "a\n  b\n\nc\n");
+    }
+
+    #[test]
+    fn dedent_non_ascii_whitespace() {
+        // Ideographic space is whitespace but not indentation
+        assert_eq!(dedent("\n  \u{3000}a\n  b"), "\u{3000}a\nb");
+        assert_eq!(dedent("\u{3000}a\n\u{3000}b"), "\u{3000}a\n\u{3000}b");
+    }
+
+    #[test]
+    fn format_escapes_literals() {
+        let stack = BuildStack::default().build();
+        let t = stack.template().format("${{x}} {} {}", vec![
+            "%{x}".to_template_part(), stack.expr::<String>("var.y").to_template_part()
+        ]).build();
+        assert_eq!(serde_json::to_value(&t.1).unwrap(), json!("$${x} %%{x} ${var.y}"));
+    }
+
+    #[test]
+    fn if_directive() {
+        let stack = BuildStack::default().build();
+        let t = stack.template().if_then_else(stack.expr::<bool>("var.a"), |b| b.lit("yes"), |b| b.lit("no")).build();
+        assert_eq!(t.1.render(), "\"%{ if var.a }yes%{ else }no%{ endif }\"");
+        let t = stack.template().if_then(stack.expr::<bool>("var.a"), |b| b.lit("yes")).build();
+        assert_eq!(t.1.render(), "\"%{ if var.a }yes%{ endif }\"");
+    }
+
+    #[test]
+    fn nested_for_directives() {
+        let stack = BuildStack::default().build();
+        let l = ListRef::<ListRef<PrimExpr<String>>>::new(stack.shared.clone(), Expression::Ref("var.l".into()));
+        let t = stack.template().for_in(&l, |b, inner| b.for_in(inner, |b, v| b.expr(v).lit(","))).build();
+        assert_eq!(t.1.render(), "\"%{ for v0 in var.l }%{ for v1 in v0 }${v1},%{ endfor }%{ endfor }\"");
+    }
+
+    #[test]
+    fn nested_separate_template() {
+        let stack = BuildStack::default().build();
+        let l = ListRef::<PrimExpr<String>>::new(stack.shared.clone(), Expression::Ref("var.l".into()));
+        let t = stack.template().for_in(&l, |b, v| {
+            // Built from the stack rather than `b`, but must not shadow `v`
+            let inner = stack.template().for_in(&l, |b, w| b.expr(&v).expr(w)).build();
+            b.expr(inner)
+        }).build();
+        assert_eq!(
+            t.1.render(),
+            "\"%{ for v0 in var.l }${\"%{ for v1 in var.l }${v0}${v1}%{ endfor }\"}%{ endfor }\""
+        );
+    }
+
+    #[test]
+    fn strip_markers() {
+        let stack = BuildStack::default().build();
+        let t =
+            stack
+                .template()
+                .set_strip(true, false)
+                .if_then(stack.expr::<bool>("var.a"), |b| b.lit("x"))
+                .set_strip(false, true)
+                .if_then(stack.expr::<bool>("var.a"), |b| b.lit("y"))
+                .build();
+        assert_eq!(t.1.render(), "\"%{~ if var.a }x%{~ endif }%{ if var.a ~}y%{ endif ~}\"");
+    }
+
+    #[test]
+    fn heredoc() {
+        let stack = BuildStack::default().build();
+        let t = stack.template().heredoc("\n    a: {}\n      b\n", vec![stack.expr::<String>("var.a").to_template_part()]).build();
+        assert_eq!(serde_json::to_value(&t.1).unwrap(), json!("a: ${var.a}\n  b\n"));
+    }
+}
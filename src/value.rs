@@ -0,0 +1,152 @@
+use std::{
+    fmt::Debug,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+        HashSet,
+    },
+    hash::Hash,
+};
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+    Serialize,
+};
+use crate::{
+    FieldValue,
+    ListField,
+    PrimField,
+    RecField,
+    SetField,
+    Stack,
+    VariableType,
+};
+
+/// Rust types that correspond to Terraform values, used by `#[derive(TfVariables)]`
+/// and `#[derive(TfOutputs)]` to pick the Terraform type of each field.
+pub trait TfValue: Serialize + DeserializeOwned {
+    /// The field type used to assign a value of this type, ex: `ListField<PrimField<String>>`
+    /// for `Vec<String>`.
+    type F: FieldValue + 'static;
+    /// The type used to declare a variable of this type.
+    type V: VariableType + 'static;
+
+    /// Whether `null` is a valid value (true for `Option`).
+    fn nullable() -> bool {
+        false
+    }
+
+    /// Whether the value should be marked sensitive (true for `Secret`).
+    fn sensitive() -> bool {
+        false
+    }
+}
+
+macro_rules! prim_tf_value{
+    ($t: ty) => {
+        impl TfValue for $t {
+            type F = PrimField<$t>;
+            type V = $t;
+        }
+    };
+}
+
+prim_tf_value!(String);
+prim_tf_value!(bool);
+prim_tf_value!(i64);
+prim_tf_value!(f64);
+
+impl<T: TfValue> TfValue for Option<T> {
+    type F = T::F;
+    type V = T::V;
+
+    fn nullable() -> bool {
+        true
+    }
+}
+
+/// A sensitive value. Variables and outputs of this type are marked sensitive, and
+/// the value is redacted when debug printed.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct Secret<T>(pub T);
+
+impl<T> Secret<T> {
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+impl<T: TfValue> TfValue for Secret<T> {
+    type F = T::F;
+    type V = T::V;
+
+    fn nullable() -> bool {
+        T::nullable()
+    }
+
+    fn sensitive() -> bool {
+        true
+    }
+}
+
+impl<T: TfValue> TfValue for Vec<T> {
+    type F = ListField<T::F>;
+    type V = ListField<T::F>;
+}
+
+impl<T: TfValue + Ord> TfValue for BTreeSet<T> {
+    type F = SetField<T::F>;
+    type V = SetField<T::F>;
+}
+
+impl<T: TfValue + Hash + Eq> TfValue for HashSet<T> {
+    type F = SetField<T::F>;
+    type V = SetField<T::F>;
+}
+
+impl<T: TfValue> TfValue for BTreeMap<String, T> {
+    type F = RecField<T::F>;
+    type V = RecField<T::F>;
+}
+
+impl<T: TfValue> TfValue for HashMap<String, T> {
+    type F = RecField<T::F>;
+    type V = RecField<T::F>;
+}
+
+/// A struct whose fields are stack variables, implemented with
+/// `#[derive(TfVariables)]`. A value of the struct is the var-file passed to
+/// `Stack::run`.
+pub trait TfVariables: Serialize {
+    /// Generated struct with a `Variable` handle per field.
+    type Variables;
+
+    /// Declare a variable per field in the stack.
+    fn declare(stack: &mut Stack) -> Self::Variables;
+}
+
+/// For stacks without variables, ex: `stack.run(path, None::<&()>, "apply")`.
+impl TfVariables for () {
+    type Variables = ();
+
+    fn declare(_stack: &mut Stack) -> Self::Variables { }
+}
+
+/// A struct whose fields are stack outputs, implemented with `#[derive(TfOutputs)]`.
+/// Read it back with `Stack::get_output`.
+pub trait TfOutputs: DeserializeOwned {
+    /// Generated `Build*` struct with the value of each output.
+    type Build;
+}
@@ -2,7 +2,6 @@ use std::{
     cell::{
         RefCell,
     },
-    fmt::Display,
     marker::PhantomData,
     rc::Rc,
 };
@@ -13,21 +12,64 @@ use serde_json::{
     Value,
 };
 use crate::{
+    Condition,
     PrimField,
     PrimType,
     prim_ref::PrimExpr,
     StackShared,
     expr::{
         Expr,
+        Expression,
     },
     manual_expr_impls,
+    FieldValue,
+    Ref,
     SerdeSkipDefault,
     Stack,
+    StackError,
 };
 
+/// Types variables can have: primitives (`String`, `bool`, `i64`, `f64`) or field
+/// values like `ListField<PrimField<String>>`, `RecField<...>` and generated object
+/// types.
+pub trait VariableType {
+    type R: Ref;
+    /// The type of literal values, for defaults.
+    type F: FieldValue;
+
+    fn extract_variable_type() -> String;
+}
+
+macro_rules! prim_variable_type{
+    ($t: ty) => {
+        impl VariableType for $t {
+            type R = PrimExpr<$t>;
+            type F = PrimField<$t>;
+
+            fn extract_variable_type() -> String {
+                <$t as crate::TfPrimitiveType>::extract_variable_type()
+            }
+        }
+    };
+}
+
+prim_variable_type!(String);
+prim_variable_type!(bool);
+prim_variable_type!(i64);
+prim_variable_type!(f64);
+
+impl<V: FieldValue> VariableType for V {
+    type R = V::R;
+    type F = V;
+
+    fn extract_variable_type() -> String {
+        <V as FieldValue>::extract_variable_type()
+    }
+}
+
 pub(crate) trait VariableTrait {
     fn extract_tf_id(&self) -> String;
-    fn extract_value(&self) -> Value;
+    fn extract_value(&self) -> Result<Value, StackError>;
 }
 
 #[derive(Serialize)]
@@ -38,29 +80,42 @@ struct VariableImplData {
     pub nullable: PrimField<bool>,
     #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")]
     pub sensitive: PrimField<bool>,
+    #[serde(skip_serializing_if = "SerdeSkipDefault::is_default")]
+    pub ephemeral: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(skip)]
+    pub default_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub validation: Vec<Condition>,
 }
 
-struct Variable_<T: PrimType> {
+struct Variable_<T> {
     shared: StackShared,
     tf_id: String,
     data: RefCell<VariableImplData>,
     _p: PhantomData<T>,
 }
 
-pub struct Variable<T: PrimType>(Rc<Variable_<T>>);
+pub struct Variable<T>(Rc<Variable_<T>>);
 
-impl<T: PrimType> VariableTrait for Variable_<T> {
+impl<T> VariableTrait for Variable_<T> {
     fn extract_tf_id(&self) -> String {
         self.tf_id.clone()
     }
 
-    fn extract_value(&self) -> Value {
+    fn extract_value(&self) -> Result<Value, StackError> {
         let data = self.data.borrow();
-        serde_json::to_value(&*data).unwrap()
+        if let Some(e) = &data.default_error {
+            return Err(StackError::VariableDefault(self.tf_id.clone(), e.clone()));
+        }
+        Ok(serde_json::to_value(&*data).unwrap())
     }
 }
 
-impl<T: PrimType> Variable<T> {
+impl<T: VariableType> Variable<T> {
     pub fn set_nullable(self, v: impl Into<PrimField<bool>>) -> Self {
         self.0.data.borrow_mut().nullable = v.into();
         self
@@ -70,22 +125,69 @@ impl<T: PrimType> Variable<T> {
         self.0.data.borrow_mut().sensitive = v.into();
         self
     }
-}
 
-impl<T: PrimType> Expr<T> for Variable<T> {
-    fn expr_raw(&self) -> (&StackShared, String) {
-        (&self.0.shared, format!("var.{}", self.0.tf_id))
+    /// Make the variable ephemeral: it's not stored in the plan or state, and can only
+    /// be used in other ephemeral contexts.
+    pub fn set_ephemeral(self, v: bool) -> Self {
+        self.0.data.borrow_mut().ephemeral = v;
+        self
+    }
+
+    /// Set the value used if none is provided, making the variable optional. This must
+    /// be a literal value - references and other expressions cause an error when the
+    /// stack is serialized.
+    pub fn set_default(self, v: impl Into<T::F>) -> Self {
+        let mut data = self.0.data.borrow_mut();
+        match v.into().to_literal() {
+            Ok(v) => {
+                data.default = Some(v);
+                data.default_error = None;
+            },
+            Err(e) => {
+                data.default = None;
+                data.default_error = Some(e.to_string());
+            },
+        }
+        drop(data);
+        self
+    }
+
+    pub fn set_description(self, v: impl ToString) -> Self {
+        self.0.data.borrow_mut().description = Some(v.to_string());
+        self
     }
 
-    fn expr_sentinel(&self) -> String {
-        let (shared, raw) = self.expr_raw();
-        shared.add_sentinel(&raw)
+    /// Reject values where `condition` is false with `error_message`. The condition
+    /// must refer to the variable (ex: via `value()`).
+    pub fn add_validation(
+        self,
+        condition: impl Into<PrimField<bool>>,
+        error_message: impl Into<PrimField<String>>,
+    ) -> Self {
+        self.0.data.borrow_mut().validation.push(Condition {
+            condition: condition.into(),
+            error_message: error_message.into(),
+        });
+        self
+    }
+
+    /// Get a typed reference to the variable's value, ex: a `ListRef` for list
+    /// variables.
+    pub fn value(&self) -> T::R {
+        T::R::new(self.0.shared.clone(), Expression::Ref(format!("var.{}", self.0.tf_id)))
+    }
+}
+
+impl<T: PrimType> Expr<T> for Variable<T> {
+    fn expr(&self) -> (&StackShared, Expression) {
+        (&self.0.shared, Expression::Ref(format!("var.{}", self.0.tf_id)))
     }
 }
 
 impl<T: PrimType> Variable<T> {
+    /// The rendered expression text, for use in `stack.expr`.
     pub fn raw(&self) -> String {
-        self.expr_raw().1
+        self.expr().1.render()
     }
 }
 
@@ -96,7 +198,7 @@ pub struct BuildVariable {
 }
 
 impl BuildVariable {
-    pub fn build<T: PrimType + 'static>(self, stack: &mut Stack) -> Variable<T> {
+    pub fn build<T: VariableType + 'static>(self, stack: &mut Stack) -> Variable<T> {
         let out = Variable(Rc::new(Variable_ {
             shared: stack.shared.clone(),
             tf_id: self.tf_id,
@@ -104,6 +206,11 @@ impl BuildVariable {
                 r#type: T::extract_variable_type(),
                 nullable: false.into(),
                 sensitive: false.into(),
+                ephemeral: false,
+                default: None,
+                default_error: None,
+                description: None,
+                validation: vec![],
             }),
             _p: Default::default(),
         }));
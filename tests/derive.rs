@@ -0,0 +1,111 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use terrars::*;
+
+#[derive(Serialize, TfVariables)]
+#[serde(rename_all = "camelCase")]
+struct Vars {
+    /// Where to deploy.
+    my_region: String,
+    #[serde(rename = "count")]
+    instance_count: i64,
+    #[serde(skip)]
+    _local_only: bool,
+    zone: Option<String>,
+    token: Secret<String>,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize, TfOutputs)]
+#[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+struct Outs {
+    /// The endpoint.
+    url: String,
+    api_key: Secret<String>,
+}
+
+fn serialize(stack: &Stack) -> Value {
+    serde_json::from_slice(&stack.serialize().unwrap()).unwrap()
+}
+
+#[test]
+fn variables() {
+    let stack = &mut BuildStack::default().build();
+    Vars::declare(stack);
+    assert_eq!(serialize(stack)["variable"], json!({
+        "myRegion": {
+            "type": "string",
+            "nullable": false,
+            "description": "Where to deploy."
+        },
+        "count": {
+            "type": "number",
+            "nullable": false
+        },
+        "zone": {
+            "type": "string"
+        },
+        "token": {
+            "type": "string",
+            "nullable": false,
+            "sensitive": true
+        },
+        "tags": {
+            "type": "list(string)",
+            "nullable": false
+        }
+    }));
+
+    // The var-file written by `Stack::run` uses the same names
+    assert_eq!(serde_json::to_value(Vars {
+        my_region: "r".into(),
+        instance_count: 2,
+        _local_only: true,
+        zone: None,
+        token: Secret("t".into()),
+        tags: vec![],
+    }).unwrap(), json!({
+        "myRegion": "r",
+        "count": 2,
+        "zone": null,
+        "token": "t",
+        "tags": []
+    }));
+}
+
+#[test]
+fn outputs() {
+    let stack = &mut BuildStack::default().build();
+    let v = Vars::declare(stack);
+    BuildOuts {
+        url: tfformat!(stack, "https://{}", &v.my_region).into(),
+        api_key: (&v.token).into(),
+    }.build(stack);
+    assert_eq!(serialize(stack)["output"], json!({
+        "URL": {
+            "value": "https://${var.myRegion}",
+            "description": "The endpoint."
+        },
+        "API-KEY": {
+            "value": "${var.token}",
+            "sensitive": true
+        }
+    }));
+    let outs: Outs = serde_json::from_value(json!({
+        "URL": "https://x",
+        "API-KEY": "k"
+    })).unwrap();
+    assert_eq!(outs.url, "https://x");
+    assert_eq!(outs.api_key.expose(), "k");
+}
+
+#[test]
+fn rejected_attributes() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use serde::Serialize;
+use terrars::TfVariables;
+
+#[derive(Serialize, TfVariables)]
+enum Vars {
+    A,
+}
+
+fn main() { }
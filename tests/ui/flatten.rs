@@ -0,0 +1,15 @@
+use serde::Serialize;
+use terrars::TfVariables;
+
+#[derive(Serialize)]
+struct Inner {
+    a: String,
+}
+
+#[derive(Serialize, TfVariables)]
+struct Vars {
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+fn main() { }
@@ -0,0 +1,10 @@
+use serde::Serialize;
+use terrars::TfVariables;
+
+#[derive(Serialize, TfVariables)]
+struct Vars {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    a: Option<String>,
+}
+
+fn main() { }
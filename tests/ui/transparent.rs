@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use terrars::TfOutputs;
+
+#[derive(Deserialize, TfOutputs)]
+#[serde(transparent)]
+struct Outs {
+    a: String,
+}
+
+fn main() { }